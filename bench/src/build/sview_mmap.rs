@@ -1,6 +1,4 @@
-use std::fs;
 use std::path::PathBuf;
-use memmap2::MmapMut;
 use sview_fmindex::{
     FmIndexBuilder, blocks::{Block2, Block3},
     text_encoders::EncodingTable,
@@ -59,30 +57,14 @@ pub fn build_index(
         let blob_size = builder.blob_size();
         println!("Blob size: {} bytes", blob_size);
 
-        // 파일 시스템에 할당
         let output_path = data_dir.join("sview-mmap-block2.blob");
-        let file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&output_path)?;
-        
-        // 파일 크기를 blob_size로 설정
-        file.set_len(blob_size as u64)?;
-        
-        // mmap으로 메모리 매핑
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
-        
-        // Build time 측정
+
+        // Build time 측정 (임시 파일에 mmap으로 빌드한 뒤 이름 변경)
         let build_start_time = std::time::Instant::now();
-        builder.build(text.to_vec(), &mut mmap)?;
+        builder.build_to_path(text.to_vec(), &output_path)?;
         let build_time = build_start_time.elapsed().as_nanos();
         println!("Build time: {} ns", build_time);
         
-        // mmap을 디스크에 동기화
-        mmap.flush()?;
-        
         println!("Index saved to: {}", output_path.display());
     } else {
         let encoding_table = EncodingTable::from_symbols(symbols);
@@ -99,30 +81,14 @@ pub fn build_index(
         let blob_size = builder.blob_size();
         println!("Blob size: {} bytes", blob_size);
 
-        // 파일 시스템에 할당
         let output_path = data_dir.join("sview-mmap-block3.blob");
-        let file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .open(&output_path)?;
-        
-        // 파일 크기를 blob_size로 설정
-        file.set_len(blob_size as u64)?;
-        
-        // mmap으로 메모리 매핑
-        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
-        
-        // Build time 측정
+
+        // Build time 측정 (임시 파일에 mmap으로 빌드한 뒤 이름 변경)
         let build_start_time = std::time::Instant::now();
-        builder.build(text.to_vec(), &mut mmap)?;
+        builder.build_to_path(text.to_vec(), &output_path)?;
         let build_time = build_start_time.elapsed().as_nanos();
         println!("Build time: {} ns", build_time);
         
-        // mmap을 디스크에 동기화
-        mmap.flush()?;
-        
         println!("Index saved to: {}", output_path.display());
     }
 
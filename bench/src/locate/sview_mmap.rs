@@ -1,15 +1,13 @@
 use std::path::PathBuf;
 use std::io::{BufRead, Write};
-use std::fs;
-use memmap2::Mmap;
 use sview_fmindex::{
-    FmIndex, Block,
+    MappedFmIndex, MmapOptions, AccessPattern, HotSections, Block,
     blocks::{Block2, Block3},
     text_encoders::EncodingTable,
 };
 use crate::locate::{create_pattern_reader, create_result_writer, write_locations_to_file};
 
-fn locate_and_write_results<B: Block>(
+fn locate_and_write_results<B: Block + 'static>(
     data_dir: &PathBuf,
     blob_stem: &str,
 ) -> Result<PathBuf, Box<dyn std::error::Error>> {
@@ -17,33 +15,27 @@ fn locate_and_write_results<B: Block>(
     
     // Blob 로딩 시간 측정
     let load_start_time = std::time::Instant::now();
-    let file = fs::File::open(&blob_path)?;
-    let mmap = unsafe { Mmap::map(&file)? };
-    
-    #[cfg(unix)]
-    {
-        use memmap2::Advice;
-        // mmap advice: 환경변수 3개로 선택 적용 (우선순위: RANDOM > SEQUENTIAL > DONTDUMP)
-        if std::env::var("MMAP_ADVICE_RANDOM").is_ok() {
-            println!("Applying MADV_RANDOM advice to mmap");
-            mmap.advise(Advice::Random)?;
-        } else if std::env::var("MMAP_ADVICE_SEQUENTIAL").is_ok() {
-            println!("Applying MADV_SEQUENTIAL advice to mmap");
-            mmap.advise(Advice::Sequential)?;
-        } else if std::env::var("MMAP_ADVICE_DONTDUMP").is_ok() {
-            #[cfg(target_os = "linux")]
-            {
-                println!("Applying MADV_DONTDUMP advice to mmap");
-                mmap.advise(Advice::DontDump)?;
-            }
-            #[cfg(not(target_os = "linux"))]
-            {
-                println!("MADV_DONTDUMP advice is only supported on Linux. Skipping.");
-            }
-        }
+    // mmap advice: 환경변수 3개로 선택 적용 (우선순위: RANDOM > SEQUENTIAL > DONTDUMP)
+    let mut options = MmapOptions::default();
+    if std::env::var("MMAP_ADVICE_RANDOM").is_ok() {
+        println!("Applying MADV_RANDOM advice to mmap");
+        options.access_pattern = AccessPattern::Random;
+    } else if std::env::var("MMAP_ADVICE_SEQUENTIAL").is_ok() {
+        println!("Applying MADV_SEQUENTIAL advice to mmap");
+        options.access_pattern = AccessPattern::Sequential;
+    } else if std::env::var("MMAP_ADVICE_DONTDUMP").is_ok() {
+        println!("Applying MADV_DONTDUMP advice to mmap (Linux only)");
+        options.dont_dump = true;
     }
-    
-    let fm_index = FmIndex::<u32, B, EncodingTable>::load(&mmap)?;
+    // 자주 읽는 작은 섹션 (count array, k-mer table, rank checkpoints) 로딩 방식
+    match std::env::var("MMAP_HOT_SECTIONS").as_deref() {
+        Ok("prefetch") => options.hot_sections = HotSections::Prefetch,
+        Ok("warmup") => options.hot_sections = HotSections::Warmup,
+        Ok("lock") => options.hot_sections = HotSections::Lock,
+        _ => {},
+    }
+    let mapped_fm_index = MappedFmIndex::<u32, B, EncodingTable>::open(&blob_path, options)?;
+    let fm_index = mapped_fm_index.index();
     let load_time = load_start_time.elapsed().as_nanos();
     
     let result_path = data_dir.join(format!("{}-results.txt", blob_stem));
@@ -0,0 +1,125 @@
+use alloc::alloc::{self as allocator, Layout};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::{io::Read, path::Path};
+
+use crate::{Block, StableBlob};
+
+/// Zero-initialized bytes allocated with the alignment for the blob
+///
+/// `Vec<u8>` is only guaranteed to be aligned to 1 byte,
+/// so building or loading the blob of `Block*<u128>` in it can fail with the alignment error.
+/// It derefs to `[u8]`, and can be used wherever the blob slice is needed.
+pub struct AlignedBlob {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+}
+
+// SAFETY: the allocation is owned uniquely, like `Box<[u8]>`.
+unsafe impl Send for AlignedBlob {}
+unsafe impl Sync for AlignedBlob {}
+// SAFETY: the bytes are in the heap allocation, which is not moved with the owner.
+unsafe impl StableBlob for AlignedBlob {}
+
+impl AlignedBlob {
+    /// Alignment satisfying every block type
+    pub const MAX_BLOCK_ALIGN: usize = 16;
+    /// Alignment of the 2 MiB huge page
+    pub const HUGE_PAGE_ALIGN: usize = 2 * 1024 * 1024;
+
+    /// Allocate `len` zeroed bytes aligned to `align`
+    ///
+    /// # Panics
+    ///
+    /// If `align` is not a power of two, or `len` rounded up to `align` overflows `isize`.
+    pub fn zeroed(len: usize, align: usize) -> Self {
+        // The zero-sized allocation is not allowed, so at least one byte is allocated.
+        let layout = match Layout::from_size_align(len.max(1), align) {
+            Ok(layout) => layout,
+            Err(err) => panic!("Invalid layout for the blob of {} bytes aligned to {}: {}", len, align, err),
+        };
+        // SAFETY: the size of the layout is not zero.
+        let ptr = unsafe { allocator::alloc_zeroed(layout) };
+        let ptr = match NonNull::new(ptr) {
+            Some(ptr) => ptr,
+            None => allocator::handle_alloc_error(layout),
+        };
+        Self { ptr, len, layout }
+    }
+    /// Allocate `len` zeroed bytes aligned for the block type
+    pub fn for_block<B: Block>(len: usize) -> Self {
+        Self::zeroed(len, B::ALIGN_SIZE)
+    }
+    /// Copy the bytes into the new allocation aligned to `align`
+    pub fn copy_from_slice(bytes: &[u8], align: usize) -> Self {
+        let mut blob = Self::zeroed(bytes.len(), align);
+        blob.copy_from_slice(bytes);
+        blob
+    }
+    /// Read the whole file into the new allocation aligned to `align`
+    ///
+    /// Use `MAX_BLOCK_ALIGN` when the block type of the blob is not known.
+    #[cfg(feature = "std")]
+    pub fn read_from_path(path: impl AsRef<Path>, align: usize) -> std::io::Result<Self> {
+        let mut file = std::fs::File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "file is larger than the address space"))?;
+        let mut blob = Self::zeroed(len, align);
+        file.read_exact(&mut blob)?;
+        Ok(blob)
+    }
+
+    /// Alignment of the allocation
+    pub fn align(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl Deref for AlignedBlob {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the allocation has at least `len` initialized bytes.
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+impl DerefMut for AlignedBlob {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // SAFETY: the allocation has at least `len` initialized bytes, and is borrowed uniquely.
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+impl AsRef<[u8]> for AlignedBlob {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+impl AsMut<[u8]> for AlignedBlob {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl Clone for AlignedBlob {
+    fn clone(&self) -> Self {
+        Self::copy_from_slice(self, self.align())
+    }
+}
+
+impl Drop for AlignedBlob {
+    fn drop(&mut self) {
+        // SAFETY: the pointer was allocated with the same layout.
+        unsafe { allocator::dealloc(self.ptr.as_ptr(), self.layout) };
+    }
+}
+
+impl core::fmt::Debug for AlignedBlob {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AlignedBlob")
+            .field("len", &self.len)
+            .field("align", &self.align())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,160 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::marker::PhantomData;
+use crate::{
+    FmIndex, LoadError, VerifyError, BlobInfo,
+    // traits
+    Position, Block, TextEncoder,
+    components::{TypeDescriptor, ByteOrder, match_type_descriptor},
+    load_from_blob::read_prefix,
+};
+
+/// FM-index with the types chosen at runtime
+///
+/// The position, block and text encoder types are read from the type descriptor of the blob,
+/// so that any supported blob can be loaded without knowing how it was built.
+/// Positions are returned as `u64` regardless of the position type.
+pub struct AnyFmIndex<'a> {
+    inner: Box<dyn DynFmIndex<'a> + Send + Sync + 'a>,
+}
+
+impl<'a> AnyFmIndex<'a> {
+    /// Load fm-index from blob with the types in its header
+    pub fn load(blob: &'a [u8]) -> Result<Self, LoadError> {
+        let (_, type_descriptor, _) = read_prefix(blob)?;
+        let inner = dyn_types(&type_descriptor)?.load(blob)?;
+        Ok(Self { inner })
+    }
+
+    /// Types that the blob was built with
+    pub fn type_descriptor(&self) -> TypeDescriptor {
+        self.inner.type_descriptor()
+    }
+    /// Count the number of occurrences with the pattern
+    pub fn count(&self, pattern: &[u8]) -> u64 {
+        self.inner.count(pattern)
+    }
+    /// Locate all occurrences with the pattern
+    pub fn locate(&self, pattern: &[u8]) -> Vec<u64> {
+        let mut buffer = Vec::new();
+        self.inner.locate_to_buffer(pattern, &mut buffer);
+        buffer
+    }
+    /// Locate all occurrences with the pattern & write to buffer
+    pub fn locate_to_buffer(&self, pattern: &[u8], buffer: &mut Vec<u64>) {
+        self.inner.locate_to_buffer(pattern, buffer);
+    }
+    /// Locate the occurrences with the pattern lazily
+    pub fn locate_iter(&self, pattern: &[u8]) -> Box<dyn DoubleEndedIterator<Item = u64> + '_> {
+        self.inner.locate_iter(pattern)
+    }
+    /// Verify the checksums of every header, body and metadata section in blob
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        self.inner.verify()
+    }
+    /// Validate the structural invariants that the queries rely on
+    pub fn validate_structure(&self) -> Result<(), VerifyError> {
+        self.inner.validate_structure()
+    }
+    /// Returns the original blob from which this index was loaded.
+    pub fn blob(&self) -> &'a [u8] {
+        self.inner.blob()
+    }
+    /// Returns the metadata borrowed from the blob, if the blob was built with it.
+    pub fn metadata(&self) -> Option<&'a [u8]> {
+        self.inner.metadata()
+    }
+}
+
+// `AnyFmIndex` is shared across threads, as `FmIndex` is.
+const fn assert_send_sync<T: Send + Sync>() {}
+const _: () = assert_send_sync::<AnyFmIndex<'static>>();
+
+impl core::fmt::Debug for AnyFmIndex<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("AnyFmIndex")
+            .field("type_descriptor", &self.type_descriptor())
+            .finish_non_exhaustive()
+    }
+}
+
+// Object safe interface of `FmIndex`
+pub(crate) trait DynFmIndex<'a> {
+    fn type_descriptor(&self) -> TypeDescriptor;
+    fn count(&self, pattern: &[u8]) -> u64;
+    fn locate_to_buffer(&self, pattern: &[u8], buffer: &mut Vec<u64>);
+    fn locate_iter<'s>(&'s self, pattern: &[u8]) -> Box<dyn DoubleEndedIterator<Item = u64> + 's>;
+    fn verify(&self) -> Result<(), VerifyError>;
+    fn validate_structure(&self) -> Result<(), VerifyError>;
+    fn blob(&self) -> &'a [u8];
+    fn metadata(&self) -> Option<&'a [u8]>;
+}
+
+impl<'a, P: Position, B: Block, E: TextEncoder> DynFmIndex<'a> for FmIndex<'a, P, B, E> {
+    fn type_descriptor(&self) -> TypeDescriptor {
+        self.type_descriptor
+    }
+    fn count(&self, pattern: &[u8]) -> u64 {
+        FmIndex::count(self, pattern).as_u64()
+    }
+    fn locate_to_buffer(&self, pattern: &[u8], buffer: &mut Vec<u64>) {
+        buffer.extend(FmIndex::locate_iter(self, pattern).map(|position| position.as_u64()));
+    }
+    fn locate_iter<'s>(&'s self, pattern: &[u8]) -> Box<dyn DoubleEndedIterator<Item = u64> + 's> {
+        Box::new(FmIndex::locate_iter(self, pattern).map(|position| position.as_u64()))
+    }
+    fn verify(&self) -> Result<(), VerifyError> {
+        FmIndex::verify(self)
+    }
+    fn validate_structure(&self) -> Result<(), VerifyError> {
+        FmIndex::validate_structure(self)
+    }
+    fn blob(&self) -> &'a [u8] {
+        FmIndex::blob(self)
+    }
+    fn metadata(&self) -> Option<&'a [u8]> {
+        FmIndex::metadata(self)
+    }
+}
+
+// ================================================
+// Dispatch by the type descriptor
+// ================================================
+// Object safe interface of the operations generic over the types
+//  - The type descriptor is matched to the supported types only in `dyn_types`,
+//    and every entry point without the types dispatches through it.
+pub(crate) trait DynTypes {
+    fn load<'a>(&self, blob: &'a [u8]) -> Result<Box<dyn DynFmIndex<'a> + Send + Sync + 'a>, LoadError>;
+    fn inspect(&self, blob: &[u8]) -> Result<BlobInfo, LoadError>;
+    fn convert_endianness(&self, blob: &[u8], new_blob: &mut [u8], byte_order: ByteOrder) -> Result<ByteOrder, LoadError>;
+    fn upgraded_blob_size(&self, old_blob: &[u8]) -> Result<usize, LoadError>;
+    fn upgrade_blob(&self, old_blob: &[u8], new_blob: &mut [u8]) -> Result<(), LoadError>;
+}
+
+struct Types<P, B, E>(PhantomData<(P, B, E)>);
+
+impl<P: Position + 'static, B: Block + 'static, E: TextEncoder + 'static> DynTypes for Types<P, B, E> {
+    fn load<'a>(&self, blob: &'a [u8]) -> Result<Box<dyn DynFmIndex<'a> + Send + Sync + 'a>, LoadError> {
+        Ok(Box::new(FmIndex::<P, B, E>::load(blob)?))
+    }
+    fn inspect(&self, blob: &[u8]) -> Result<BlobInfo, LoadError> {
+        BlobInfo::inspect_as::<P, B, E>(blob)
+    }
+    fn convert_endianness(&self, blob: &[u8], new_blob: &mut [u8], byte_order: ByteOrder) -> Result<ByteOrder, LoadError> {
+        crate::convert_endianness::convert_as::<P, B, E>(blob, new_blob, byte_order)
+    }
+    fn upgraded_blob_size(&self, old_blob: &[u8]) -> Result<usize, LoadError> {
+        FmIndex::<P, B, E>::upgraded_blob_size(old_blob)
+    }
+    fn upgrade_blob(&self, old_blob: &[u8], new_blob: &mut [u8]) -> Result<(), LoadError> {
+        FmIndex::<P, B, E>::upgrade_blob(old_blob, new_blob)
+    }
+}
+
+/// Operations of the types described by the type descriptor
+pub(crate) fn dyn_types(type_descriptor: &TypeDescriptor) -> Result<&'static dyn DynTypes, LoadError> {
+    match_type_descriptor!(
+        type_descriptor,
+        |P, B, E| Ok(&Types::<P, B, E>(PhantomData)),
+        Err(LoadError::UnsupportedType(*type_descriptor))
+    )
+}
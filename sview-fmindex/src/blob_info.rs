@@ -0,0 +1,134 @@
+use alloc::{vec, vec::Vec};
+use zerocopy::FromBytes;
+
+use crate::{
+    // traits
+    Position, Block,
+    components::{
+        Header, PrefixAlign, TypeDescriptor, TextEncoder, SectionKind, PREFIX_SIZE,
+    },
+    text_encoders::EncodingTable,
+    load_from_blob::{BlobHeaders, read_prefix},
+    any_fm_index::dyn_types,
+    LoadError,
+};
+
+/// Summary of the fm-index blob
+///
+/// Read from the headers without knowing the types that the blob was built with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobInfo {
+    /// Format version of the blob (major, minor)
+    pub format_version: (u8, u8),
+    /// Types that the blob was built with
+    pub type_descriptor: TypeDescriptor,
+    /// Length of the indexed text
+    pub text_len: u64,
+    pub symbol_count: u32,
+    pub suffix_array_sampling_ratio: u32,
+    pub lookup_table_kmer_size: u32,
+    /// Total size of the blob in bytes
+    pub blob_size: usize,
+    /// Headers and body sections in the order of the blob
+    ///
+    /// The sections unknown to this version are named "unknown section".
+    pub sections: Vec<SectionInfo>,
+    /// Symbol to index mapping, if the blob was built with `EncodingTable`
+    pub encoding_table: Option<EncodingTable>,
+}
+
+/// Position and size of a section in the blob
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionInfo {
+    pub name: &'static str,
+    /// Offset in bytes from the start of the blob
+    pub offset: usize,
+    /// Size in bytes, excluding the padding for alignment
+    pub size: usize,
+}
+
+impl BlobInfo {
+    /// Inspect the blob
+    ///
+    /// Only the headers are read, so the blob does not have to be aligned.
+    pub fn inspect(blob: &[u8]) -> Result<Self, LoadError> {
+        let (_, type_descriptor, _) = read_prefix(blob)?;
+        dyn_types(&type_descriptor)?.inspect(blob)
+    }
+
+    pub(crate) fn inspect_as<P: Position, B: Block, E: TextEncoder>(blob: &[u8]) -> Result<Self, LoadError> {
+        let (headers, [count_array_body, _, _]) = BlobHeaders::<E>::read::<P, B>(blob)?;
+        let BlobHeaders {
+            magic_number,
+            type_descriptor,
+            toc,
+            text_encoder,
+            count_array_header,
+            suffix_array_header,
+            ..
+        } = &headers;
+
+        // Sections
+        let mut sections = vec![
+            prefix_section("magic number", 0, magic_number),
+            prefix_section("type descriptor", magic_number.aligned_size::<PrefixAlign>(), type_descriptor),
+        ];
+        if !magic_number.is_untyped_version() {
+            sections.push(SectionInfo {
+                name: "table of contents",
+                offset: PREFIX_SIZE,
+                size: toc.raw_size(),
+            });
+        }
+        let mut listed_sections: Vec<SectionInfo> = toc.entries().iter().map(|entry| SectionInfo {
+            name: SectionKind::from_code(entry.kind).map_or("unknown section", SectionKind::name),
+            offset: entry.offset as usize,
+            size: entry.length as usize,
+        }).collect();
+        listed_sections.sort_by_key(|section| section.offset);
+        sections.extend(listed_sections);
+
+        // The last element of the count array is the text length
+        let text_len = match count_array_header.count_array_len.checked_sub(1) {
+            Some(last_index) => {
+                let last_offset = last_index as usize * core::mem::size_of::<P>();
+                count_array_body.get(last_offset..)
+                    .and_then(|bytes| P::read_from_prefix(bytes).ok())
+                    .map(|(text_len, _)| text_len.as_u64())
+                    .ok_or(LoadError::InvalidSection("count array"))?
+            },
+            None => 0,
+        };
+        let encoding_table = if E::KIND == EncodingTable::KIND {
+            EncodingTable::read_from_bytes(text_encoder.as_bytes()).ok()
+        } else {
+            None
+        };
+
+        Ok(Self {
+            format_version: magic_number.version(),
+            type_descriptor: *type_descriptor,
+            text_len,
+            symbol_count: count_array_header.symbol_count,
+            suffix_array_sampling_ratio: suffix_array_header.sampling_ratio,
+            lookup_table_kmer_size: count_array_header.lookup_table_kmer_size,
+            blob_size: blob.len(),
+            sections,
+            encoding_table,
+        })
+    }
+
+    /// Get the section by name
+    pub fn section(&self, name: &str) -> Option<&SectionInfo> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+}
+
+// Section of the magic number or type descriptor
+fn prefix_section(name: &'static str, offset: usize, header: &impl Header) -> SectionInfo {
+    SectionInfo {
+        name,
+        offset,
+        size: header.as_bytes().len(),
+    }
+}
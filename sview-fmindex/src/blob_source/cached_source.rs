@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
+use std::sync::{Condvar, Mutex, PoisonError};
+
+use super::BlobSource;
+
+/// Blob source keeping the pages read from the inner source
+///
+/// At most `capacity` pages of `page_size` bytes are kept,
+/// and the least recently used page is evicted first.
+/// The memory is bounded by `page_size * capacity`, regardless of the blob size.
+/// The missed pages are read from the inner source without the lock of the cache,
+/// so that the reads of the other pages are not blocked by them.
+pub struct CachedSource<S: BlobSource> {
+    inner: S,
+    size: u64,
+    page_size: usize,
+    capacity: usize,
+    cache: Mutex<PageCache>,
+    // Notified when a page being read is done
+    page_read: Condvar,
+}
+
+/// Counts of the page reads of `CachedSource`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Pages found in the cache
+    pub hits: u64,
+    /// Pages read from the inner source
+    pub misses: u64,
+    /// Pages in the cache
+    pub cached_pages: usize,
+}
+
+// Pages by the index, with the order of the last use
+#[derive(Default)]
+struct PageCache {
+    pages: HashMap<u64, CachedPage>,
+    last_used: BTreeMap<u64, u64>, // tick -> page index
+    reading: HashSet<u64>, // pages being read from the inner source
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+struct CachedPage {
+    bytes: Box<[u8]>,
+    tick: u64,
+}
+
+impl<S: BlobSource> CachedSource<S> {
+    /// Default size of the page (64 KiB)
+    pub const DEFAULT_PAGE_SIZE: usize = 64 * 1024;
+
+    /// Cache the pages of the inner source
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` or `capacity` is zero.
+    pub fn new(inner: S, page_size: usize, capacity: usize) -> io::Result<Self> {
+        assert!(page_size > 0, "Page size of the cache must be positive");
+        assert!(capacity > 0, "Capacity of the cache must be positive");
+        let size = inner.size()?;
+        Ok(Self {
+            inner,
+            size,
+            page_size,
+            capacity,
+            cache: Mutex::new(PageCache::default()),
+            page_read: Condvar::new(),
+        })
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+    pub fn stats(&self) -> CacheStats {
+        let cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        CacheStats {
+            hits: cache.hits,
+            misses: cache.misses,
+            cached_pages: cache.pages.len(),
+        }
+    }
+    /// Drop every cached page
+    pub fn clear(&self) {
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        cache.pages.clear();
+        cache.last_used.clear();
+    }
+
+    // Read the page from the inner source
+    fn read_page(&self, page_index: u64) -> io::Result<Box<[u8]>> {
+        let page_start = page_index * self.page_size as u64;
+        let page_len = (self.size - page_start).min(self.page_size as u64) as usize;
+        let mut bytes = vec![0; page_len].into_boxed_slice();
+        self.inner.read_exact_at(page_start, &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+impl<S: BlobSource> BlobSource for CachedSource<S> {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.size)
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let end = offset.checked_add(buf.len() as u64)
+            .filter(|end| *end <= self.size)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        let page_size = self.page_size as u64;
+        let mut buf_offset = 0;
+        for page_index in offset / page_size..=(end - 1) / page_size {
+            // The lock is released while the missed page is read,
+            // and the concurrent queries of the same page wait for the read, not to read it twice.
+            let page = loop {
+                if cache.touch(page_index) {
+                    break &cache.pages[&page_index].bytes[..];
+                }
+                if cache.reading.contains(&page_index) {
+                    cache = self.page_read.wait(cache).unwrap_or_else(PoisonError::into_inner);
+                    continue;
+                }
+                cache.misses += 1;
+                cache.reading.insert(page_index);
+                drop(cache);
+                let result = self.read_page(page_index);
+                cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+                cache.reading.remove(&page_index);
+                self.page_read.notify_all();
+                break cache.insert(page_index, result?, self.capacity);
+            };
+            let page_start = page_index * page_size;
+            let copy_start = (offset.max(page_start) - page_start) as usize;
+            let copy_end = (end.min(page_start + page_size) - page_start) as usize;
+            let copy_len = copy_end - copy_start;
+            buf[buf_offset..buf_offset + copy_len].copy_from_slice(&page[copy_start..copy_end]);
+            buf_offset += copy_len;
+        }
+        Ok(())
+    }
+}
+
+impl PageCache {
+    // Mark the page as the most recently used, and return whether it is cached
+    fn touch(&mut self, page_index: u64) -> bool {
+        match self.pages.get_mut(&page_index) {
+            Some(page) => {
+                self.tick += 1;
+                self.last_used.remove(&page.tick);
+                self.last_used.insert(self.tick, page_index);
+                page.tick = self.tick;
+                self.hits += 1;
+                true
+            },
+            None => false,
+        }
+    }
+    // Insert the page, evicting the least recently used pages over the capacity
+    fn insert(&mut self, page_index: u64, bytes: Box<[u8]>, capacity: usize) -> &[u8] {
+        while self.pages.len() >= capacity {
+            match self.last_used.pop_first() {
+                Some((_, evicted_index)) => { self.pages.remove(&evicted_index); },
+                None => break,
+            }
+        }
+        self.tick += 1;
+        let tick = self.tick;
+        self.last_used.insert(tick, page_index);
+        &self.pages.entry(page_index).insert_entry(CachedPage { bytes, tick }).into_mut().bytes
+    }
+}
+
+impl<S: BlobSource> std::fmt::Debug for CachedSource<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CachedSource")
+            .field("size", &self.size)
+            .field("page_size", &self.page_size)
+            .field("capacity", &self.capacity)
+            .field("stats", &self.stats())
+            .finish_non_exhaustive()
+    }
+}
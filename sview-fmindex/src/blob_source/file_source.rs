@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use super::BlobSource;
+
+/// Blob source reading the file with positioned reads (`pread`)
+///
+/// The file is not mapped, and the reads do not move a shared cursor,
+/// so that the source can be read from many threads.
+/// Wrap it in `CachedSource` to keep the pages read by the previous queries.
+#[derive(Debug)]
+pub struct FileSource {
+    file: File,
+    size: u64,
+}
+
+impl FileSource {
+    /// Open the file at the path
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(File::open(path)?)
+    }
+    /// Read the opened file
+    ///
+    /// The file must not be resized while it is read.
+    pub fn new(file: File) -> io::Result<Self> {
+        let size = file.metadata()?.len();
+        Ok(Self { file, size })
+    }
+    pub fn into_file(self) -> File {
+        self.file
+    }
+}
+
+impl BlobSource for FileSource {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.size)
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        read_exact_at(&self.file, offset, buf)
+    }
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()> {
+    // `seek_read` moves the cursor of the file, which is not used by the source.
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_read(file, buf, offset) {
+            Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+            Ok(read_len) => {
+                buf = &mut buf[read_len..];
+                offset += read_len as u64;
+            },
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+#[cfg(not(any(unix, windows)))]
+fn read_exact_at(_file: &File, _offset: u64, _buf: &mut [u8]) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
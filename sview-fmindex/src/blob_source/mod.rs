@@ -0,0 +1,68 @@
+use std::io;
+use std::sync::Arc;
+
+mod file_source;
+pub use file_source::FileSource;
+mod cached_source;
+pub use cached_source::{CachedSource, CacheStats};
+
+/// Storage serving the byte ranges of a blob
+///
+/// `SourcedFmIndex` reads the headers and the small sections on open,
+/// and the blocks and suffix array items that each query touches,
+/// instead of mapping or loading the whole blob.
+/// The ranges do not have to be aligned.
+pub trait BlobSource {
+    /// Total size of the blob in bytes
+    fn size(&self) -> io::Result<u64>;
+    /// Fill `buf` with the bytes of the blob from `offset`
+    ///
+    /// Fails with `UnexpectedEof` if the blob ends before `buf` is filled.
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()>;
+}
+
+impl BlobSource for [u8] {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.len() as u64)
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let bytes = usize::try_from(offset).ok()
+            .and_then(|start| Some(start..start.checked_add(buf.len())?))
+            .and_then(|range| self.get(range))
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        buf.copy_from_slice(bytes);
+        Ok(())
+    }
+}
+impl BlobSource for Vec<u8> {
+    fn size(&self) -> io::Result<u64> {
+        self.as_slice().size()
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        self.as_slice().read_exact_at(offset, buf)
+    }
+}
+impl<S: BlobSource + ?Sized> BlobSource for &S {
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read_exact_at(offset, buf)
+    }
+}
+impl<S: BlobSource + ?Sized> BlobSource for Box<S> {
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read_exact_at(offset, buf)
+    }
+}
+impl<S: BlobSource + ?Sized> BlobSource for Arc<S> {
+    fn size(&self) -> io::Result<u64> {
+        (**self).size()
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        (**self).read_exact_at(offset, buf)
+    }
+}
@@ -8,3 +8,7 @@ mod suffix_array_config;
 pub use suffix_array_config::SuffixArrayConfig;
 mod lookup_table_config;
 pub use lookup_table_config::LookupTableConfig;
+mod suffix_sort_config;
+pub use suffix_sort_config::SuffixSortConfig;
+mod tuned_config;
+pub use tuned_config::{QuerySpeed, TunedConfig};
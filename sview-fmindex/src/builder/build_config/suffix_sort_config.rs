@@ -0,0 +1,44 @@
+use std::path::PathBuf;
+
+use super::BuildError;
+
+/// Configuration for where the suffixes are sorted
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SuffixSortConfig {
+    /// Sort in memory
+    #[default]
+    InMemory,
+    /// Sort in the scratch files of the directory, keeping the sort buffers within the memory budget in bytes
+    ///
+    /// The text stays in memory, but the suffix array is never held in memory as a whole.
+    /// The scratch files take up to about 72 bytes per symbol, and are removed after the build.
+    ExternalMemory {
+        temp_dir: PathBuf,
+        memory_budget: usize,
+    },
+}
+
+impl SuffixSortConfig {
+    /// Smallest memory budget for the external memory
+    pub const MIN_MEMORY_BUDGET: usize = 1 << 20;
+    // Read or write buffer of a scratch file
+    pub(crate) const IO_BUFFER_SIZE: usize = 64 * 1024;
+    // The buffers outside of the memory budget at once: two readers of the ranks and a writer of a sorted run
+    pub(crate) const IO_BUFFER_MEMORY: usize = 3 * Self::IO_BUFFER_SIZE;
+
+    /// Check the memory budget
+    pub fn validate(&self) -> Result<(), BuildError> {
+        match self {
+            Self::InMemory => Ok(()),
+            Self::ExternalMemory { memory_budget, .. } => {
+                if *memory_budget < Self::MIN_MEMORY_BUDGET {
+                    Err(BuildError::InvalidConfig(format!(
+                        "Memory budget for the external memory must be at least {} bytes", Self::MIN_MEMORY_BUDGET,
+                    )))
+                } else {
+                    Ok(())
+                }
+            },
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use super::{SuffixArrayConfig, LookupTableConfig};
+
+/// Preference of the query speed, when the configuration is tuned to the blob size budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuerySpeed {
+    /// Smallest blob: sparse suffix array, and no lookup table
+    Compact,
+    /// Suffix array sampled every 4 or more positions, and a small lookup table
+    #[default]
+    Balanced,
+    /// Densest suffix array and largest lookup table within the budget
+    Fast,
+}
+
+impl QuerySpeed {
+    // Smallest sampling ratio to try
+    pub(crate) fn min_sampling_ratio(&self) -> u32 {
+        match self {
+            Self::Compact => 32,
+            Self::Balanced => 4,
+            Self::Fast => 1,
+        }
+    }
+    // Largest count of the k-mers in the lookup table, relative to the text length
+    //  - A table larger than the text does not narrow the search further.
+    pub(crate) fn max_lookup_table_len(&self, text_len: usize) -> usize {
+        match self {
+            Self::Compact => 0,
+            Self::Balanced => text_len / 16,
+            Self::Fast => text_len,
+        }
+    }
+    // Vector widths in the order of the preference, when the sampling ratios are the same
+    pub(crate) fn vector_bits_in_preference(&self) -> [u32; 3] {
+        match self {
+            Self::Compact => [128, 64, 32],
+            Self::Balanced | Self::Fast => [64, 128, 32],
+        }
+    }
+}
+
+/// Configuration tuned to the blob size budget by `FmIndexBuilder::tune_for_blob_size_budget`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TunedConfig {
+    /// Bit width of the vector in the block (32, 64 or 128)
+    pub vector_bits: u32,
+    pub suffix_array_config: SuffixArrayConfig,
+    pub lookup_table_config: LookupTableConfig,
+    /// Size of the blob built with the configuration
+    pub blob_size: usize,
+}
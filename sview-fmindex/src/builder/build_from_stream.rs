@@ -0,0 +1,64 @@
+use std::io::{self, Read};
+
+use super::{FmIndexBuilder, BuildError};
+use crate::{Position, Block, TextEncoder};
+
+impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
+    /// Build the FM-index from the text read from the reader, and write to the provided blob slice
+    ///
+    /// The text is read into a single buffer of the text length given to `new`,
+    /// which the build transforms in place.
+    /// If the length is not known in advance, count it in a first pass (e.g. from the file size).
+    pub fn build_from_reader(&self, reader: impl Read, blob: &mut [u8]) -> Result<(), BuildError> {
+        let text = self.read_text(reader)?;
+        self.build(text, blob)
+    }
+    /// Build the FM-index from the chunks of text, and write to the provided blob slice
+    ///
+    /// The chunks (e.g. the records of FASTA) are joined into a single buffer of the text length given to `new`,
+    /// without concatenating them beforehand.
+    /// If the length is not known in advance, sum the lengths of the chunks in a first pass.
+    pub fn build_from_chunks<I, C>(&self, chunks: I, blob: &mut [u8]) -> Result<(), BuildError> where
+        I: IntoIterator<Item = C>,
+        C: AsRef<[u8]>,
+    {
+        let text = self.join_chunks(chunks)?;
+        self.build(text, blob)
+    }
+
+    // The buffer has a spare byte for the sentinel appended by the suffix array construction.
+    fn text_buffer(&self) -> Vec<u8> {
+        Vec::with_capacity(self.text_len + 1)
+    }
+    fn read_text(&self, reader: impl Read) -> Result<Vec<u8>, BuildError> {
+        let mut text = self.text_buffer();
+        let mut reader = reader.take(self.text_len as u64);
+        reader.read_to_end(&mut text)?;
+        if text.len() != self.text_len {
+            return Err(BuildError::UnmatchedTextLength(self.text_len, text.len()));
+        }
+        // Only one more byte is read to detect the longer text
+        if io::copy(&mut reader.into_inner().take(1), &mut io::sink())? != 0 {
+            return Err(BuildError::TextLongerThanLength(self.text_len));
+        }
+        Ok(text)
+    }
+    fn join_chunks<I, C>(&self, chunks: I) -> Result<Vec<u8>, BuildError> where
+        I: IntoIterator<Item = C>,
+        C: AsRef<[u8]>,
+    {
+        let mut text = self.text_buffer();
+        for chunk in chunks {
+            let chunk = chunk.as_ref();
+            // The rest of the chunks are not taken after the length is exceeded.
+            if chunk.len() > self.text_len - text.len() {
+                return Err(BuildError::TextLongerThanLength(self.text_len));
+            }
+            text.extend_from_slice(chunk);
+        }
+        if text.len() != self.text_len {
+            return Err(BuildError::UnmatchedTextLength(self.text_len, text.len()));
+        }
+        Ok(text)
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use memmap2::MmapMut;
+
+use super::{FmIndexBuilder, BuildError};
+use crate::{Position, Block, TextEncoder};
+
+impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
+    /// Build the FM-index into the file at the path
+    ///
+    /// The blob is built in a temporary file next to the path through the memory map,
+    /// and renamed to the path after it is flushed, so that readers never see a half-built blob.
+    /// On Unix, the directory is synced after the rename, so that the renamed file survives a crash.
+    /// The temporary file is removed on error.
+    pub fn build_to_path(&self, text: Vec<u8>, path: impl AsRef<Path>) -> Result<(), BuildError> {
+        let path = path.as_ref();
+        let temp_file = TempFile::create_next_to(path)?;
+        temp_file.file.set_len(self.blob_size() as u64)?;
+
+        // SAFETY: the temporary file is created by this call, and is not modified by others while mapped.
+        let mut mmap = unsafe { MmapMut::map_mut(&temp_file.file)? };
+        // The memory map is aligned to the page, which satisfies the alignment of every block.
+        self.build(text, &mut mmap)?;
+        mmap.flush()?;
+        drop(mmap);
+
+        temp_file.persist(path)?;
+        Ok(())
+    }
+    /// Build the FM-index and write the blob to the writer
+    ///
+    /// The blob is built in memory, and written from the current position of the writer.
+    /// If writing fails, the writer is rewound to the position where the blob started,
+    /// but the bytes already written are left in it, so that the output after the position is invalid.
+    /// Truncate it to the position (e.g. with `File::set_len`) to discard them.
+    pub fn build_to_writer(&self, text: Vec<u8>, mut writer: impl Write + Seek) -> Result<(), BuildError> {
+        let blob = self.build_to_aligned_vec(text)?;
+
+        let start_position = writer.stream_position()?;
+        if let Err(err) = writer.write_all(&blob).and_then(|_| writer.flush()) {
+            let _ = writer.seek(SeekFrom::Start(start_position));
+            return Err(err.into());
+        }
+        Ok(())
+    }
+}
+
+// File removed on drop, unless it is renamed to the destination
+struct TempFile {
+    path: PathBuf,
+    file: File,
+    persisted: bool,
+}
+
+impl TempFile {
+    fn create_next_to(path: &Path) -> io::Result<Self> {
+        let file_name = path.file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+            .to_string_lossy();
+        let mut attempt = 0_u32;
+        loop {
+            let temp_path = path.with_file_name(
+                format!(".{}.{}-{}.tmp", file_name, std::process::id(), attempt)
+            );
+            match OpenOptions::new().read(true).write(true).create_new(true).open(&temp_path) {
+                Ok(file) => return Ok(Self { path: temp_path, file, persisted: false }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 1024 => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    fn persist(mut self, path: &Path) -> io::Result<()> {
+        self.file.sync_all()?;
+        fs::rename(&self.path, path)?;
+        self.persisted = true;
+        // The rename is durable only after the directory entry is synced.
+        #[cfg(unix)]
+        {
+            let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            File::open(parent)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if !self.persisted {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
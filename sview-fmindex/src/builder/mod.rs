@@ -1,18 +1,30 @@
 use std::marker::PhantomData;
+use std::sync::Arc;
 
 use crate::{
+    AlignedBlob,
     // traits
     Position, Block,
     components::{
-        Header, View,
+        Header, View, PrefixAlign, ByteOrder, Toc, SectionKind, PREFIX_SIZE,
         // headers
-        MagicNumber, TextEncoder, CountArrayHeader, SuffixArrayHeader, BwmHeader,
+        MagicNumber, TypeDescriptor, TextEncoder, CountArrayHeader, SuffixArrayHeader, BwmHeader, ChecksumHeader,
         // views
         CountArrayView, SuffixArrayView, BwmView,
+        match_type_descriptor,
     },
 };
 
 pub mod build_config;
+mod build_to_file;
+mod build_from_stream;
+mod progress;
+pub use progress::{BuildPhase, ProgressSink, CancellationToken};
+pub(crate) use progress::{BuildMonitor, PhaseProgress, REPORT_INTERVAL};
+
+// Allowance for the queues and jobs of each thread in the estimate of the peak memory
+#[cfg(feature = "parallel")]
+const THREAD_WORKING_MEMORY: usize = 16 * 1024;
 
 /// Builder for FM-index
 pub struct FmIndexBuilder<P: Position, B: Block, E: TextEncoder> {
@@ -20,14 +32,21 @@ pub struct FmIndexBuilder<P: Position, B: Block, E: TextEncoder> {
     text_len: usize,
     symbol_count: u32,
     magic_number: MagicNumber,
+    type_descriptor: TypeDescriptor,
     text_encoder: E,
     // Configs
     suffix_array_config: build_config::SuffixArrayConfig,
     lookup_table_config: build_config::LookupTableConfig,
+    suffix_sort_config: build_config::SuffixSortConfig,
     // Changeable after init
     count_array_header: CountArrayHeader,
     suffix_array_header: SuffixArrayHeader,
     bwm_header: BwmHeader,
+    metadata: Option<Vec<u8>>,
+    #[cfg(feature = "parallel")]
+    thread_count: Option<usize>,
+    progress_sink: Option<Arc<dyn ProgressSink>>,
+    cancellation_token: Option<CancellationToken>,
     // Phantom data
     _phantom: PhantomData<(P, B)>,
 }
@@ -39,10 +58,19 @@ pub enum BuildError {
     #[error("The symbol count ({1}) exceeds the maximum for the chosen block type ({0}). Try using a larger block type or reducing the number of symbols.")]
     SymbolCountOver(u32, u32),
 
+    /// The text is too long for the chosen position type, with the sentinel and the marker of the suffix sort.
+    #[error("The text length ({1}) exceeds the maximum for the chosen position type ({0}). Try using a larger position type.")]
+    TextLengthOver(u64, usize),
+
     /// The length of the provided text does not match the length declared during builder initialization.
     #[error("Mismatched text length: expected {0} bytes, but got {1} bytes.")]
     UnmatchedTextLength(usize, usize),
 
+    /// The text read from a stream or chunks is longer than the length declared during builder initialization.
+    /// The rest of the stream or chunks is not read to count its length.
+    #[error("Mismatched text length: expected {0} bytes, but got more.")]
+    TextLongerThanLength(usize),
+
     /// The provided blob slice has an incorrect size.
     #[error("Incorrect blob size: expected {0} bytes, but got {1} bytes.")]
     InvalidBlobSize(usize, usize),
@@ -54,6 +82,14 @@ pub enum BuildError {
     /// An invalid build configuration was provided.
     #[error("Invalid build configuration: {0}")]
     InvalidConfig(String),
+
+    /// The build was cancelled by the `CancellationToken`.
+    #[error("The build was cancelled.")]
+    Cancelled,
+
+    /// Reading the text, or creating, writing or renaming the output failed.
+    #[error("I/O error while reading the text or writing the blob: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
@@ -71,6 +107,12 @@ impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
         if symbol_count > B::MAX_SYMBOL {
             return Err(BuildError::SymbolCountOver(B::MAX_SYMBOL, symbol_count));
         }
+        // The suffix sort marks the empty slots with the largest position,
+        // so the positions up to the sentinel must be smaller than it.
+        let max_text_len = (u64::MAX >> (u64::BITS - P::BITS)) - 2;
+        if text_len as u64 > max_text_len {
+            return Err(BuildError::TextLengthOver(max_text_len, text_len));
+        }
 
         // Generate headers
         let (count_array_header, suffix_array_header, bwm_header) = Self::generate_headers(
@@ -85,18 +127,148 @@ impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
             text_len,
             symbol_count,
             magic_number: MagicNumber::new(),
+            type_descriptor: TypeDescriptor::of::<P, B, E>(),
             text_encoder,
             // Configs
             lookup_table_config,
             suffix_array_config,
+            suffix_sort_config: build_config::SuffixSortConfig::default(),
             // Changeable after init
             count_array_header,
             suffix_array_header,
             bwm_header,
+            metadata: None,
+            #[cfg(feature = "parallel")]
+            thread_count: None,
+            progress_sink: None,
+            cancellation_token: None,
             // Phantom data
             _phantom: PhantomData,
         })
     }
+    /// New builder of which the suffix array sampling and the lookup table are tuned to the blob size budget
+    ///
+    /// The densest suffix array allowed by the `query_speed` that fits in the budget is taken,
+    /// and the lookup table takes the rest of the budget, up to the size allowed by the `query_speed`.
+    /// The vector width is fixed by `B`; `tune_for_blob_size_budget` picks it among the widths.
+    pub fn with_blob_size_budget(
+        text_len: usize,
+        symbol_count: u32,
+        text_encoder: E,
+        blob_size_budget: usize,
+        query_speed: build_config::QuerySpeed,
+    ) -> Result<Self, BuildError> {
+        let (suffix_array_config, lookup_table_config, _) = Self::tune_for_block::<B>(
+            text_len,
+            symbol_count,
+            &text_encoder,
+            blob_size_budget,
+            query_speed,
+        )?;
+        Self::new(text_len, symbol_count, text_encoder)?
+            .set_suffix_array_config(suffix_array_config)?
+            .set_lookup_table_config(lookup_table_config)
+    }
+    /// Tune the vector width of the block, the suffix array sampling and the lookup table to the blob size budget
+    ///
+    /// Each vector width is tuned as in `with_blob_size_budget`, keeping the vector count of `B`.
+    /// The width of the densest suffix array is taken, and the ties are broken by the `query_speed`.
+    /// The builder is then created with the block of `TunedConfig::vector_bits`.
+    pub fn tune_for_blob_size_budget(
+        text_len: usize,
+        symbol_count: u32,
+        text_encoder: &E,
+        blob_size_budget: usize,
+        query_speed: build_config::QuerySpeed,
+    ) -> Result<build_config::TunedConfig, BuildError> {
+        let mut best: Option<build_config::TunedConfig> = None;
+        let mut error = None;
+        for vector_bits in query_speed.vector_bits_in_preference() {
+            let type_descriptor = TypeDescriptor::of::<P, B, E>().with_vector_bits(vector_bits as u8);
+            let tuned = match_type_descriptor!(
+                type_descriptor,
+                |_P, BB, _E| Self::tune_for_block::<BB>(text_len, symbol_count, text_encoder, blob_size_budget, query_speed),
+                Err(BuildError::InvalidConfig(format!("Unsupported types to tune: {}", type_descriptor)))
+            );
+            match tuned {
+                Ok((suffix_array_config, lookup_table_config, blob_size)) => {
+                    let tuned = build_config::TunedConfig { vector_bits, suffix_array_config, lookup_table_config, blob_size };
+                    let sampling_ratio = |config: &build_config::TunedConfig| config.suffix_array_config.sampling_ratio().unwrap_or(u32::MAX);
+                    if best.as_ref().is_none_or(|best| sampling_ratio(&tuned) < sampling_ratio(best)) {
+                        best = Some(tuned);
+                    }
+                },
+                Err(err) => { error.get_or_insert(err); },
+            }
+        }
+        match (best, error) {
+            (Some(best), _) => Ok(best),
+            (None, Some(err)) => Err(err),
+            (None, None) => unreachable!("At least one vector width is tuned"),
+        }
+    }
+    // Suffix array and lookup table configs of the block within the budget, and the blob size
+    fn tune_for_block<BB: Block>(
+        text_len: usize,
+        symbol_count: u32,
+        text_encoder: &E,
+        blob_size_budget: usize,
+        query_speed: build_config::QuerySpeed,
+    ) -> Result<(build_config::SuffixArrayConfig, build_config::LookupTableConfig, usize), BuildError> {
+        if symbol_count > BB::MAX_SYMBOL {
+            return Err(BuildError::SymbolCountOver(BB::MAX_SYMBOL, symbol_count));
+        }
+        let blob_size_of = |suffix_array_config: &build_config::SuffixArrayConfig, lookup_table_config: &build_config::LookupTableConfig| {
+            let (count_array_header, suffix_array_header, bwm_header) = FmIndexBuilder::<P, BB, E>::generate_headers(
+                text_len,
+                symbol_count,
+                suffix_array_config,
+                lookup_table_config,
+            )?;
+            let toc = Toc::of_current_layout::<P, BB, E>(text_encoder, &count_array_header, &suffix_array_header, &bwm_header, None);
+            Ok::<_, BuildError>(toc.aligned_end::<BB>() as usize)
+        };
+
+        // The densest suffix array that fits
+        let mut sampling_ratio = query_speed.min_sampling_ratio();
+        let (suffix_array_config, mut blob_size) = loop {
+            let suffix_array_config = if sampling_ratio == 1 {
+                build_config::SuffixArrayConfig::Uncompressed
+            } else {
+                build_config::SuffixArrayConfig::Compressed(sampling_ratio)
+            };
+            let blob_size = blob_size_of(&suffix_array_config, &build_config::LookupTableConfig::None)?;
+            if blob_size <= blob_size_budget {
+                break (suffix_array_config, blob_size);
+            }
+            // The suffix array is no longer dominant beyond the text length.
+            match sampling_ratio.checked_mul(2) {
+                Some(next_sampling_ratio) if sampling_ratio as usize <= text_len => sampling_ratio = next_sampling_ratio,
+                _ => return Err(BuildError::InvalidConfig(format!(
+                    "Blob size budget ({} bytes) is smaller than the smallest blob ({} bytes)", blob_size_budget, blob_size,
+                ))),
+            }
+        };
+
+        // The largest lookup table in the rest of the budget
+        //  - The alignment of the table can take a few more bytes, so that the k-mer size is lowered until it fits.
+        let max_lookup_table_size = (blob_size_budget - blob_size).min(
+            query_speed.max_lookup_table_len(text_len).saturating_mul(size_of::<P>())
+        );
+        let mut kmer_size = build_config::LookupTableConfig::MaxMemory(max_lookup_table_size).kmer_size::<P>(symbol_count)?;
+        let mut lookup_table_config = build_config::LookupTableConfig::None;
+        while kmer_size >= 2 {
+            let config = build_config::LookupTableConfig::KmerSize(kmer_size);
+            let size = blob_size_of(&suffix_array_config, &config)?;
+            if size <= blob_size_budget {
+                lookup_table_config = config;
+                blob_size = size;
+                break;
+            }
+            kmer_size -= 1;
+        }
+        Ok((suffix_array_config, lookup_table_config, blob_size))
+    }
     fn generate_headers(
         text_len: usize,
         symbol_count: u32,
@@ -157,42 +329,170 @@ impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
             ..self
         })
     }
+    /// Sort the suffixes in memory (default), or in the scratch files for the texts larger than the memory
+    ///
+    /// The blob is identical in both.
+    pub fn set_suffix_sort_config(self, config: build_config::SuffixSortConfig) -> Result<Self, BuildError> {
+        config.validate()?;
+        Ok(Self {
+            suffix_sort_config: config,
+            ..self
+        })
+    }
+    /// Report the progress of each phase of the build to the sink
+    ///
+    /// A closure of `Fn(BuildPhase, u64, u64)` is a sink, receiving the phase, processed and total counts.
+    pub fn set_progress_sink(self, progress_sink: impl ProgressSink + 'static) -> Self {
+        Self {
+            progress_sink: Some(Arc::new(progress_sink)),
+            ..self
+        }
+    }
+    /// Return `BuildError::Cancelled` from the build once the token is cancelled
+    pub fn set_cancellation_token(self, cancellation_token: CancellationToken) -> Self {
+        Self {
+            cancellation_token: Some(cancellation_token),
+            ..self
+        }
+    }
+    /// Embed the user-defined bytes in the blob, such as the provenance of the text
+    ///
+    /// The metadata is read back with `FmIndex::metadata` without copying.
+    pub fn set_metadata(self, metadata: impl Into<Vec<u8>>) -> Self {
+        Self {
+            metadata: Some(metadata.into()),
+            ..self
+        }
+    }
+    /// Build on the new thread pool of `thread_count` threads
+    ///
+    /// Without it, the build runs on the current rayon thread pool (the global one by default).
+    /// Zero uses the number of logical CPUs, as `rayon::ThreadPoolBuilder::num_threads`.
+    /// The encoding, counting, block vectorization and rank checkpoints are built in parallel,
+    /// while the suffix sorting itself runs on one thread.
+    #[cfg(feature = "parallel")]
+    pub fn set_thread_count(self, thread_count: usize) -> Self {
+        Self {
+            thread_count: Some(thread_count),
+            ..self
+        }
+    }
 
     // ================================================
     // Blob size calculation
     // ================================================
     /// Calculate the total size of the blob in bytes
     pub fn blob_size(&self) -> usize {
-        self.header_size() + self.body_size()
+        self.toc().aligned_end::<B>() as usize
     }
-    // Header size in bytes
-    fn header_size(&self) -> usize {
-        self.magic_number.aligned_size::<B>()
-        + self.text_encoder.aligned_size::<B>()
-        + self.count_array_header.aligned_size::<B>()
-        + self.suffix_array_header.aligned_size::<B>()
-        + self.bwm_header.aligned_size::<B>()
+    /// Estimate the peak memory of the build in bytes, including the text and the blob
+    ///
+    /// The working memory depends on the suffix sorting backend:
+    /// - SA-IS (default): the suffix array of `P` for each symbol,
+    ///   with the suffix types and buckets of each level of the recursion, and then the BWT taken from it.
+    /// - `fastbwt`: two suffix arrays of `i64` for each symbol.
+    /// - `SuffixSortConfig::ExternalMemory`: the memory budget with the buffers of the scratch files, and the sampled suffix array.
+    ///
+    /// The estimate is an upper bound of the memory allocated by the build.
+    /// The text is assumed to have the capacity for the sentinel, as in `build_from_reader`.
+    pub fn estimate_peak_build_memory(&self) -> usize {
+        let n = self.text_len + 1; // +1 for sentinel
+        let position_size = size_of::<P>();
+        let sampled_suffix_array_size = self.suffix_array_header.suffix_array_raw_size::<P>();
+
+        // Threads: the queues and jobs of each thread
+        #[cfg(not(feature = "parallel"))]
+        let thread_memory = 0;
+        #[cfg(feature = "parallel")]
+        let thread_count = self.thread_count.filter(|thread_count| *thread_count > 0)
+            .unwrap_or_else(rayon::current_num_threads);
+        #[cfg(feature = "parallel")]
+        let thread_memory = thread_count * THREAD_WORKING_MEMORY;
+
+        // Counting: the k-mer tables of each thread
+        #[cfg(not(feature = "parallel"))]
+        let counting_memory = 0;
+        #[cfg(feature = "parallel")]
+        let counting_memory = {
+            let kmer_count_table_len = self.count_array_header.kmer_count_table_len as usize;
+            if kmer_count_table_len.saturating_mul(thread_count) <= self.text_len {
+                kmer_count_table_len * thread_count * position_size
+            } else {
+                0
+            }
+        };
+
+        // Suffix sorting & BWT
+        let sorting_memory = match &self.suffix_sort_config {
+            build_config::SuffixSortConfig::InMemory => {
+                #[cfg(not(feature = "fastbwt"))]
+                let sorting_memory = {
+                    // The bits of the suffix types at each level, of which the length is at most half of the level above,
+                    // with a word of rounding at each of the levels
+                    let suffix_types_memory = n / 4 + usize::BITS as usize * size_of::<u64>();
+                    // The buckets of the bytes at the top level,
+                    // and of the names at each level below, of which the lengths sum up to less than `n`
+                    let buckets_memory = (u8::MAX as usize + 1 + n) * position_size;
+                    // The sampled suffix array is collected to a new vector on the threads.
+                    let sampled_memory = if cfg!(feature = "parallel") { sampled_suffix_array_size } else { 0 };
+                    n * position_size + (suffix_types_memory + buckets_memory).max(n + sampled_memory)
+                };
+                #[cfg(feature = "fastbwt")]
+                let sorting_memory = 2 * n * size_of::<i64>();
+                sorting_memory
+            },
+            build_config::SuffixSortConfig::ExternalMemory { memory_budget, .. } => {
+                memory_budget + build_config::SuffixSortConfig::IO_BUFFER_MEMORY + sampled_suffix_array_size
+            },
+        };
+
+        self.blob_size() + n + thread_memory + counting_memory.max(sorting_memory)
     }
-    // Body size in bytes
-    fn body_size(&self) -> usize {
-        CountArrayView::<P>::aligned_body_size::<B>(&self.count_array_header)
-        + SuffixArrayView::<P>::aligned_body_size::<B>(&self.suffix_array_header) 
-        + BwmView::<P, B>::aligned_body_size::<B>(&self.bwm_header)
+    // Offsets and sizes of the sections
+    fn toc(&self) -> Toc {
+        Toc::of_current_layout::<P, B, E>(
+            &self.text_encoder,
+            &self.count_array_header,
+            &self.suffix_array_header,
+            &self.bwm_header,
+            self.metadata.as_ref().map(|metadata| metadata.len()),
+        )
     }
 
     // ================================================
     // Build
     // ================================================
     /// Build the FM-index and write to the provided blob slice
+    ///
+    /// The text is transformed in place, after the sentinel is appended to it.
+    /// Leave the spare capacity of one byte in the text (e.g. `Vec::with_capacity(text_len + 1)`),
+    /// or the text is copied once to a larger buffer.
     pub fn build<'a>(
         &self,
-        mut text: Vec<u8>,
+        text: Vec<u8>,
         blob: &'a mut [u8],
+    ) -> Result<(), BuildError> {
+        #[cfg(feature = "parallel")]
+        if let Some(thread_count) = self.thread_count {
+            let thread_pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(thread_count)
+                .build()
+                .map_err(|err| BuildError::InvalidConfig(format!("Failed to build the thread pool: {}", err)))?;
+            return thread_pool.install(|| self.build_on_current_threads(text, blob));
+        }
+        self.build_on_current_threads(text, blob)
+    }
+    fn build_on_current_threads(
+        &self,
+        mut text: Vec<u8>,
+        blob: &mut [u8],
     ) -> Result<(), BuildError> {
         // Check text length
         if text.len() != self.text_len {
             return Err(BuildError::UnmatchedTextLength(self.text_len, text.len()));
         }
+        // Capacity for the sentinel, appended by the suffix sorting
+        text.reserve_exact(1);
 
         // Check alignment
         let required_alignment = B::ALIGN_SIZE;
@@ -209,29 +509,33 @@ impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
         }
 
         // 1) Write headers
-        let mut header_start_index = 0;
-        // Magic number
-        let mut header_end_index = self.magic_number.aligned_size::<B>();
-        self.magic_number.write_to_blob(&mut blob[header_start_index..header_end_index]);
+        let toc = self.toc();
+        let section_start = |kind: SectionKind| match toc.entry(kind) {
+            Some(entry) => entry.offset as usize,
+            None => panic!("Table of contents must list the {}", kind.name()),
+        };
+        // Magic number & type descriptor
+        self.magic_number.write_to_blob(blob);
+        self.type_descriptor.write_to_blob(&mut blob[self.magic_number.aligned_size::<PrefixAlign>()..]);
+        // Table of contents
+        toc.write_to_blob(&mut blob[PREFIX_SIZE..], ByteOrder::NATIVE);
         // Encoding table
-        header_start_index = header_end_index;
-        header_end_index += self.text_encoder.aligned_size::<B>();
-        self.text_encoder.write_to_blob(&mut blob[header_start_index..header_end_index]);
+        self.text_encoder.write_to_blob(&mut blob[section_start(SectionKind::TextEncoder)..]);
         // Count array header
-        header_start_index = header_end_index;
-        header_end_index += self.count_array_header.aligned_size::<B>();
-        self.count_array_header.write_to_blob(&mut blob[header_start_index..header_end_index]);
+        self.count_array_header.write_to_blob(&mut blob[section_start(SectionKind::CountArrayHeader)..]);
         // Suffix array header
-        header_start_index = header_end_index;
-        header_end_index += self.suffix_array_header.aligned_size::<B>();
-        self.suffix_array_header.write_to_blob(&mut blob[header_start_index..header_end_index]);
+        self.suffix_array_header.write_to_blob(&mut blob[section_start(SectionKind::SuffixArrayHeader)..]);
         // BWM header
-        header_start_index = header_end_index;
-        header_end_index += self.bwm_header.aligned_size::<B>();
-        self.bwm_header.write_to_blob(&mut blob[header_start_index..header_end_index]);
+        self.bwm_header.write_to_blob(&mut blob[section_start(SectionKind::BwmHeader)..]);
+        // Checksum header (written after the bodies)
 
         // 2) Build & write bodies
-        let mut body_start_index = header_end_index;
+        let monitor = BuildMonitor {
+            progress_sink: self.progress_sink.as_deref(),
+            cancellation_token: self.cancellation_token.as_ref(),
+        };
+        monitor.check_cancelled()?;
+        let mut body_start_index = section_start(SectionKind::CountArray);
         let mut body_end_index = body_start_index + CountArrayView::<P>::aligned_body_size::<B>(&self.count_array_header);
         // Count array
         //  - encode text with encoding table
@@ -240,26 +544,58 @@ impl<P: Position, B: Block, E: TextEncoder> FmIndexBuilder<P, B, E> {
             &mut text,
             &self.text_encoder,
             &mut blob[body_start_index..body_end_index],
-        );
+            &monitor,
+        )?;
         // Suffix array
         //  - burrow-wheeler transform
         //  - get sentinel character index
-        body_start_index = body_end_index;
+        body_start_index = section_start(SectionKind::SuffixArray);
         body_end_index = body_start_index + SuffixArrayView::<P>::aligned_body_size::<B>(&self.suffix_array_header);
 
-        let sentinel_index = self.suffix_array_header.write_to_blob_and_get_sentinel_index::<P>(
-            &mut text,
-            &mut blob[body_start_index..body_end_index],
-        );
+        let sentinel_index = match &self.suffix_sort_config {
+            build_config::SuffixSortConfig::InMemory => {
+                self.suffix_array_header.write_to_blob_and_get_sentinel_index::<P>(
+                    &mut text,
+                    &mut blob[body_start_index..body_end_index],
+                    &monitor,
+                )?
+            },
+            build_config::SuffixSortConfig::ExternalMemory { temp_dir, memory_budget } => {
+                self.suffix_array_header.write_to_blob_and_get_sentinel_index_in_external_memory::<P>(
+                    &mut text,
+                    &mut blob[body_start_index..body_end_index],
+                    temp_dir,
+                    *memory_budget,
+                    &monitor,
+                )?
+            },
+        };
         // BWM
-        body_start_index = body_end_index;
+        body_start_index = section_start(SectionKind::SentinelIndex);
         body_end_index = body_start_index + BwmView::<P, B>::aligned_body_size::<B>(&self.bwm_header);
         self.bwm_header.encode_bwm_body::<P, B>(
             text,
             sentinel_index, 
             &mut blob[body_start_index..body_end_index],
-        );
+            &monitor,
+        )?;
+
+        // 3) Write metadata
+        if let Some(metadata) = &self.metadata {
+            let metadata_start_index = section_start(SectionKind::Metadata);
+            blob[metadata_start_index..metadata_start_index + metadata.len()].copy_from_slice(metadata);
+        }
+
+        // 4) Write checksums of the written headers and bodies
+        let checksum_header = ChecksumHeader::of_sections(blob, &toc);
+        checksum_header.write_to_blob(&mut blob[section_start(SectionKind::ChecksumHeader)..]);
 
         Ok(())
     }
+    /// Build the FM-index into the new blob allocated with the alignment of the block
+    pub fn build_to_aligned_vec(&self, text: Vec<u8>) -> Result<AlignedBlob, BuildError> {
+        let mut blob = AlignedBlob::for_block::<B>(self.blob_size());
+        self.build(text, &mut blob)?;
+        Ok(blob)
+    }
 }
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use super::BuildError;
+
+/// Phase of the build, reported to the `ProgressSink`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuildPhase {
+    /// Encoding the text, and counting the symbols and k-mers (in symbols)
+    Counting,
+    /// Sorting the suffixes (in suffixes)
+    ///
+    /// The in-memory sort is reported at its stages,
+    /// and the sort in external memory by the suffixes of which the ranks are final, over all rounds of the prefix doubling.
+    SuffixSorting,
+    /// Taking the Burrows-Wheeler transformed text (in rows)
+    Bwt,
+    /// Writing the sampled suffix array (in sampled items)
+    SuffixArraySampling,
+    /// Vectorizing the blocks and the rank checkpoints (in blocks)
+    BlockEncoding,
+}
+
+/// Receiver of the progress of the build
+///
+/// The phases are reported in order, each starting from zero and ending at the total.
+/// It can be called from the threads of the `parallel` build, so it should return quickly.
+pub trait ProgressSink: Send + Sync {
+    fn report(&self, phase: BuildPhase, processed: u64, total: u64);
+}
+
+impl<F: Fn(BuildPhase, u64, u64) + Send + Sync> ProgressSink for F {
+    fn report(&self, phase: BuildPhase, processed: u64, total: u64) {
+        self(phase, processed, total)
+    }
+}
+
+/// Token to cancel the build from another thread
+///
+/// The build checks the token between the chunks of each phase,
+/// and returns `BuildError::Cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// ================================================
+// Monitor passed to the phases of the build
+// ================================================
+/// Number of items processed between the reports and the checks of the cancellation
+pub(crate) const REPORT_INTERVAL: usize = 1 << 20;
+
+#[derive(Default)]
+pub(crate) struct BuildMonitor<'a> {
+    pub progress_sink: Option<&'a dyn ProgressSink>,
+    pub cancellation_token: Option<&'a CancellationToken>,
+}
+
+impl BuildMonitor<'_> {
+    pub fn check_cancelled(&self) -> Result<(), BuildError> {
+        match self.cancellation_token {
+            Some(token) if token.is_cancelled() => Err(BuildError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+    /// Start the phase, reporting zero
+    pub fn start(&self, phase: BuildPhase, total: u64) -> Result<PhaseProgress<'_>, BuildError> {
+        let progress = PhaseProgress { monitor: self, phase, total, processed: AtomicU64::new(0) };
+        progress.report(0)?;
+        Ok(progress)
+    }
+}
+
+pub(crate) struct PhaseProgress<'m> {
+    monitor: &'m BuildMonitor<'m>,
+    phase: BuildPhase,
+    total: u64,
+    processed: AtomicU64,
+}
+
+impl PhaseProgress<'_> {
+    /// Add the processed items, which can be called from many threads
+    pub fn add(&self, count: u64) -> Result<(), BuildError> {
+        let processed = self.processed.fetch_add(count, Ordering::Relaxed) + count;
+        self.report(processed.min(self.total))
+    }
+    /// Set the processed items
+    pub fn set(&self, processed: u64) -> Result<(), BuildError> {
+        self.processed.store(processed, Ordering::Relaxed);
+        self.report(processed.min(self.total))
+    }
+    pub fn check_cancelled(&self) -> Result<(), BuildError> {
+        self.monitor.check_cancelled()
+    }
+    pub fn finish(self) -> Result<(), BuildError> {
+        self.report(self.total)
+    }
+    fn report(&self, processed: u64) -> Result<(), BuildError> {
+        self.monitor.check_cancelled()?;
+        if let Some(progress_sink) = self.monitor.progress_sink {
+            progress_sink.report(self.phase, processed, self.total);
+        }
+        Ok(())
+    }
+}
@@ -13,6 +13,7 @@ impl<V: Vector> Aligned for Block2<V> {
 impl<V: Vector> Block for Block2<V> {
     const BLOCK_LEN: u32 = V::BLOCK_LEN;
     const MAX_SYMBOL: u32 = 4;
+    const VECTOR_COUNT: u32 = 2;
 
     #[inline]
     fn vectorize<P: Position>(text_chunk: &[u8], rank_pre_counts: &mut [P]) -> Self {
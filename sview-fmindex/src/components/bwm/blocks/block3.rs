@@ -13,6 +13,7 @@ impl<V: Vector> Aligned for Block3<V> {
 impl<V: Vector> Block for Block3<V> {
     const BLOCK_LEN: u32 = V::BLOCK_LEN;
     const MAX_SYMBOL: u32 = 8;
+    const VECTOR_COUNT: u32 = 3;
 
     #[inline]
     fn vectorize<P: Position>(text_chunk: &[u8], rank_pre_counts: &mut [P]) -> Self {
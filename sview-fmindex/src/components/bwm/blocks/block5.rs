@@ -13,6 +13,7 @@ impl<V: Vector> Aligned for Block5<V> {
 impl<V: Vector> Block for Block5<V> {
     const BLOCK_LEN: u32 = V::BLOCK_LEN;
     const MAX_SYMBOL: u32 = 32;
+    const VECTOR_COUNT: u32 = 5;
 
     #[inline]
     fn vectorize<P: Position>(text_chunk: &[u8], rank_pre_counts: &mut [P]) -> Self {
@@ -13,6 +13,7 @@ impl<V: Vector> Aligned for Block6<V> {
 impl<V: Vector> Block for Block6<V> {
     const BLOCK_LEN: u32 = V::BLOCK_LEN;
     const MAX_SYMBOL: u32 = 64;
+    const VECTOR_COUNT: u32 = 6;
 
     #[inline]
     fn vectorize<P: Position>(text_chunk: &[u8], rank_pre_counts: &mut [P]) -> Self {
@@ -11,13 +11,13 @@ Using the more small bits makes the algorithm faster, but the size of the struct
 pub trait Vector:
     Sized
     + Send + Sync
-    + std::ops::Not<Output = Self>
-    + std::ops::BitAnd<Output = Self>
-    + std::ops::Shr<Output = Self>
-    + std::ops::ShlAssign<Self>
-    + std::ops::ShlAssign<u32>
-    + std::ops::ShrAssign<u32>
-    + std::ops::AddAssign<Self>
+    + core::ops::Not<Output = Self>
+    + core::ops::BitAnd<Output = Self>
+    + core::ops::Shr<Output = Self>
+    + core::ops::ShlAssign<Self>
+    + core::ops::ShlAssign<u32>
+    + core::ops::ShrAssign<u32>
+    + core::ops::AddAssign<Self>
     + num_traits::int::PrimInt
     + zerocopy::FromBytes
     + zerocopy::IntoBytes
@@ -1,5 +1,11 @@
-use crate::Position;
-use super::{Aligned,Header, View};
+use alloc::vec;
+use core::convert::Infallible;
+use zerocopy::IntoBytes;
+
+use crate::{Position, LoadError, VerifyError};
+use super::{Aligned,Header, View, ChecksumHeader, CountArrayView, SectionSize, SectionKind, items_size};
+#[cfg(feature = "std")]
+use crate::{BuildError, builder::{BuildMonitor, BuildPhase, REPORT_INTERVAL}};
 
 pub mod blocks;
 
@@ -28,9 +34,10 @@ pub struct BwmView<'a, P: Position, B: Block> {
 /// Block to compress the Burrows-Wheeler transformed text
 /// 
 /// Define the maximum symbol count that can be indexed by the block.
-pub trait Block: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutable + Aligned {
+pub trait Block: Send + Sync + zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutable + Aligned {
     const BLOCK_LEN: u32; // Length of block
     const MAX_SYMBOL: u32; // Maximum symbol count that can be indexed by the block
+    const VECTOR_COUNT: u32; // Count of vectors in block
 
     // Build
     fn vectorize<P: Position>(bwt_text: &[u8], rank_pre_counts: &mut [P]) -> Self;
@@ -42,30 +49,71 @@ pub trait Block: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutable
 
 impl BwmHeader {
     fn sentinel_index_raw_size<P: Position>(&self) -> usize {
-        std::mem::size_of::<P>()
+        core::mem::size_of::<P>()
     }
     fn sentinel_index_aligned_size<P: Position, A: Aligned>(&self) -> usize {
         A::aligned_size(self.sentinel_index_raw_size::<P>())
     }
     fn rank_checkpoints_raw_size<P: Position>(&self) -> usize {
-        self.rank_checkpoints_len as usize * std::mem::size_of::<P>()
+        items_size(self.rank_checkpoints_len, core::mem::size_of::<P>())
     }
     fn rank_checkpoints_aligned_size<P: Position, A: Aligned>(&self) -> usize {
         A::aligned_size(self.rank_checkpoints_raw_size::<P>())
     }
     fn blocks_raw_size<B: Block>(&self) -> usize {
-        self.blocks_len as usize * std::mem::size_of::<B>()
+        items_size(self.blocks_len, core::mem::size_of::<B>())
     }
     fn blocks_aligned_size<B: Block, A: Aligned>(&self) -> usize {
         A::aligned_size(self.blocks_raw_size::<B>())
     }
+    pub fn body_sections<P: Position, B: Block, A: Aligned>(&self) -> [SectionSize; 3] {
+        [
+            SectionSize {
+                kind: SectionKind::SentinelIndex,
+                raw_size: self.sentinel_index_raw_size::<P>(),
+                aligned_size: self.sentinel_index_aligned_size::<P, A>(),
+                item_size: core::mem::size_of::<P>(),
+            },
+            SectionSize {
+                kind: SectionKind::RankCheckpoints,
+                raw_size: self.rank_checkpoints_raw_size::<P>(),
+                aligned_size: self.rank_checkpoints_aligned_size::<P, A>(),
+                item_size: core::mem::size_of::<P>(),
+            },
+            SectionSize {
+                kind: SectionKind::Blocks,
+                raw_size: self.blocks_raw_size::<B>(),
+                aligned_size: self.blocks_aligned_size::<B, A>(),
+                item_size: B::BLOCK_LEN as usize / 8, // size of vector
+            },
+        ]
+    }
 }
 
-impl Header for BwmHeader {}
+impl Header for BwmHeader {
+    fn swap_byte_order(&mut self) {
+        self.symbol_count = self.symbol_count.swap_bytes();
+        self.rank_checkpoints_len = self.rank_checkpoints_len.swap_bytes();
+        self.blocks_len = self.blocks_len.swap_bytes();
+    }
+}
+
+// ================================================
+// Validate
+// ================================================
+impl BwmHeader {
+    /// Check that the lengths in header are consistent
+    pub fn is_valid(&self, symbol_count: u32) -> bool {
+        self.symbol_count == symbol_count
+        && self.blocks_len >= 1
+        && self.blocks_len.checked_mul(self.symbol_count as u64) == Some(self.rank_checkpoints_len)
+    }
+}
 
 // ================================================
 // Build
 // ================================================
+#[cfg(feature = "std")]
 impl BwmHeader {
     #[inline]
     pub fn new<P: Position, B: Block>(
@@ -93,7 +141,8 @@ impl BwmHeader {
         bwt_text: Vec<u8>, // burrow-wheeler transformed text
         sentinel_index: P, // Sentinel index in bwt_text
         blob: &mut [u8],
-    ) {
+        monitor: &BuildMonitor,
+    ) -> Result<(), BuildError> {
         let last_offset = {
             let rem = bwt_text.len() % B::BLOCK_LEN as usize;
             if rem == 0 {
@@ -120,18 +169,62 @@ impl BwmHeader {
             (left, right)
         };
 
-        let mut rank_pre_counts = vec![P::ZERO; self.symbol_count as usize];
-        let mut rank_checkpoints_start_index = 0;
+        let block_count = bwt_text.len().div_ceil(B::BLOCK_LEN as usize);
+        let blocks_per_report = (REPORT_INTERVAL / B::BLOCK_LEN as usize).max(1);
+        let progress = monitor.start(BuildPhase::BlockEncoding, block_count as u64)?;
 
-        bwt_text.chunks(B::BLOCK_LEN as usize).enumerate().for_each(|(block_idx, text_chunk)| {
-            rank_checkpoints_blob[
-                rank_checkpoints_start_index..rank_checkpoints_start_index+(self.symbol_count as usize)
-            ].copy_from_slice(&rank_pre_counts);
-            rank_checkpoints_start_index += self.symbol_count as usize;
+        #[cfg(not(feature = "parallel"))]
+        let (rank_pre_counts, rank_checkpoints_start_index) = {
+            let mut rank_pre_counts = vec![P::ZERO; self.symbol_count as usize];
+            let mut rank_checkpoints_start_index = 0;
 
-            let block = B::vectorize(text_chunk, &mut rank_pre_counts);
-            blocks_blob[block_idx] = block;
-        });
+            for (block_idx, text_chunk) in bwt_text.chunks(B::BLOCK_LEN as usize).enumerate() {
+                rank_checkpoints_blob[
+                    rank_checkpoints_start_index..rank_checkpoints_start_index+(self.symbol_count as usize)
+                ].copy_from_slice(&rank_pre_counts);
+                rank_checkpoints_start_index += self.symbol_count as usize;
+
+                let block = B::vectorize(text_chunk, &mut rank_pre_counts);
+                blocks_blob[block_idx] = block;
+
+                if (block_idx + 1) % blocks_per_report == 0 {
+                    progress.set((block_idx + 1) as u64)?;
+                }
+            }
+            (rank_pre_counts, rank_checkpoints_start_index)
+        };
+        #[cfg(feature = "parallel")]
+        let (rank_pre_counts, rank_checkpoints_start_index) = {
+            use rayon::prelude::*;
+
+            // Vectorize the blocks, writing the symbol counts of each block to its checkpoint
+            let symbol_count = self.symbol_count as usize;
+            blocks_blob.par_chunks_mut(blocks_per_report)
+                .zip(rank_checkpoints_blob.par_chunks_mut(blocks_per_report * symbol_count))
+                .zip(bwt_text.par_chunks(blocks_per_report * B::BLOCK_LEN as usize))
+                .try_for_each(|((blocks, rank_checkpoints), text)| {
+                    let mut vectorized_count = 0;
+                    blocks.iter_mut()
+                        .zip(rank_checkpoints.chunks_mut(symbol_count))
+                        .zip(text.chunks(B::BLOCK_LEN as usize))
+                        .for_each(|((block, rank_checkpoint), text_chunk)| {
+                            rank_checkpoint.fill(P::ZERO);
+                            *block = B::vectorize(text_chunk, rank_checkpoint);
+                            vectorized_count += 1;
+                        });
+                    progress.add(vectorized_count)
+                })?;
+            // Prefix sum of the counts
+            let mut rank_pre_counts = vec![P::ZERO; symbol_count];
+            rank_checkpoints_blob[..block_count * symbol_count].chunks_mut(symbol_count).for_each(|rank_checkpoint| {
+                rank_checkpoint.iter_mut().zip(rank_pre_counts.iter_mut()).for_each(|(count, pre_count)| {
+                    let block_count = *count;
+                    *count = *pre_count;
+                    *pre_count += block_count;
+                });
+            });
+            (rank_pre_counts, block_count * symbol_count)
+        };
 
         if last_offset == 0 {
             rank_checkpoints_blob[rank_checkpoints_start_index..].copy_from_slice(&rank_pre_counts);
@@ -140,6 +233,7 @@ impl BwmHeader {
             let last_block = blocks_blob.last_mut().unwrap();
             last_block.shift_last_offset(last_offset);
         }
+        progress.finish()
     }
 }
 
@@ -154,84 +248,191 @@ impl<'a, P: Position, B: Block> View<'a> for BwmView<'a, P, B> {
         + header.rank_checkpoints_aligned_size::<P, A>()
         + header.blocks_aligned_size::<B, A>()
     }
-    fn load_from_body<A: Aligned>(header: &Self::Header, body_blob: &'a [u8]) -> Self {
+    fn load_from_body<A: Aligned>(header: &Self::Header, body_blob: &'a [u8]) -> Result<Self, LoadError> {
         let symbol_with_sentinel_count = P::from_u32(header.symbol_count);
 
         // Sentinel index
         let mut body_start_index = 0;
         let mut body_end_index = header.sentinel_index_raw_size::<P>();
         let mut next_body_start_index = header.sentinel_index_aligned_size::<P, A>();
-        let sentinel_index = zerocopy::FromBytes::read_from_bytes(
-            &body_blob[body_start_index..body_end_index]
-        ).unwrap();
+        let sentinel_index = body_blob.get(body_start_index..body_end_index)
+            .and_then(|bytes| zerocopy::FromBytes::read_from_bytes(bytes).ok())
+            .ok_or(LoadError::InvalidSection("sentinel index"))?;
 
         // Rank checkpoints
         body_start_index = next_body_start_index;
         body_end_index = body_start_index + header.rank_checkpoints_raw_size::<P>();
         next_body_start_index = body_start_index + header.rank_checkpoints_aligned_size::<P, A>();
-        let rank_checkpoints: &[P] = zerocopy::FromBytes::ref_from_bytes(
-            &body_blob[body_start_index..body_end_index]
-        ).unwrap();
+        let rank_checkpoints: &[P] = body_blob.get(body_start_index..body_end_index)
+            .and_then(|bytes| zerocopy::FromBytes::ref_from_bytes(bytes).ok())
+            .ok_or(LoadError::InvalidSection("rank checkpoints"))?;
 
         // Blocks
         body_start_index = next_body_start_index;
         body_end_index = body_start_index + header.blocks_raw_size::<B>();
-        let blocks: &[B] = zerocopy::FromBytes::ref_from_bytes(
-            &body_blob[body_start_index..body_end_index]
-        ).unwrap();
+        let blocks: &[B] = body_blob.get(body_start_index..body_end_index)
+            .and_then(|bytes| zerocopy::FromBytes::ref_from_bytes(bytes).ok())
+            .ok_or(LoadError::InvalidSection("blocks"))?;
 
-        BwmView {
+        Ok(BwmView {
             symbol_with_sentinel_count,
             sentinel_index,
             rank_checkpoints,
             blocks,
+        })
+    }
+}
+
+// ================================================
+// Checksum
+// ================================================
+impl<P: Position, B: Block> BwmView<'_, P, B> {
+    pub fn write_checksums(&self, checksum_header: &mut ChecksumHeader) {
+        checksum_header.sentinel_index = ChecksumHeader::checksum_of(self.sentinel_index.as_bytes());
+        checksum_header.rank_checkpoints = ChecksumHeader::checksum_of(self.rank_checkpoints.as_bytes());
+        checksum_header.blocks = ChecksumHeader::checksum_of(self.blocks.as_bytes());
+    }
+}
+
+// ================================================
+// Validate
+// ================================================
+impl<P: Position, B: Block> BwmView<'_, P, B> {
+    pub fn validate_structure(
+        &self,
+        text_len: P,
+        count_array_view: &CountArrayView<P>,
+    ) -> Result<(), VerifyError> {
+        let symbol_count = self.symbol_with_sentinel_count.as_usize();
+        let (full_blocks_len, last_rem) = text_len.div_rem_with_u32(B::BLOCK_LEN);
+        let full_blocks_len = full_blocks_len.as_usize();
+
+        if self.blocks.len() != full_blocks_len + 1 {
+            return Err(VerifyError::InvalidStructure {
+                section: "blocks",
+                reason: "does not match the text length",
+            });
+        }
+        // The sentinel suffix comes first, so that the sentinel index is zero only for the empty text.
+        let sentinel_index_is_valid = if text_len == P::ZERO {
+            self.sentinel_index == P::ZERO
+        } else {
+            P::ONE <= self.sentinel_index && self.sentinel_index <= text_len
+        };
+        if !sentinel_index_is_valid {
+            return Err(VerifyError::InvalidStructure {
+                section: "sentinel index",
+                reason: "is out of the text",
+            });
+        }
+
+        // Rank checkpoints are the accumulated counts of preceding blocks
+        let mut rank_pre_counts = vec![P::ZERO; symbol_count];
+        for (block_idx, block) in self.blocks.iter().enumerate() {
+            let rank_checkpoints = &self.rank_checkpoints[block_idx * symbol_count..(block_idx + 1) * symbol_count];
+            if rank_checkpoints != rank_pre_counts.as_slice() {
+                return Err(VerifyError::InvalidStructure {
+                    section: "rank checkpoints",
+                    reason: "do not match the counts of preceding blocks",
+                });
+            }
+            let counted_len = if block_idx < full_blocks_len { B::BLOCK_LEN } else { last_rem };
+            if counted_len != 0 {
+                rank_pre_counts.iter_mut().enumerate().for_each(|(symidx, count)| {
+                    *count += P::from_u32(block.get_remain_count_of(counted_len, symidx as u8));
+                });
+            }
+        }
+
+        // Every symbol in blocks is counted in count array
+        let is_matched_with_count_array = rank_pre_counts.iter().enumerate().all(|(symidx, &count)| {
+            count == count_array_view.symbol_count_of(symidx)
+        });
+        if !is_matched_with_count_array {
+            return Err(VerifyError::InvalidStructure {
+                section: "blocks",
+                reason: "do not match the counts of count array",
+            });
         }
+        Ok(())
     }
 }
 
 // ================================================
 // Locate
 // ================================================
-impl<'a, P: Position, B: Block> BwmView<'a, P, B> {
+/// Rank checkpoints and blocks read by the queries
+///
+/// `BwmView` reads them from the slices of blob, which never fails.
+/// The other storages can fail to read them.
+pub trait BwmItems<P: Position> {
+    type Block: Block;
+    type Error;
+
+    fn symbol_with_sentinel_count(&self) -> P;
+    fn sentinel_index(&self) -> P;
+    fn rank_checkpoint(&self, rank_idx: usize) -> Result<P, Self::Error>;
+    fn with_block<T>(&self, block_idx: usize, f: impl FnOnce(&Self::Block) -> T) -> Result<T, Self::Error>;
+
     #[inline]
-    pub fn get_next_rank(
+    fn get_next_rank(
         &self,
         mut pos: P,
         symidx: u8,
-    ) -> P {
-        if pos < self.sentinel_index {
+    ) -> Result<P, Self::Error> {
+        if pos < self.sentinel_index() {
             pos += P::ONE;
         }
-        let (quot, rem) = pos.div_rem_with_u32(B::BLOCK_LEN);
+        let (quot, rem) = pos.div_rem_with_u32(Self::Block::BLOCK_LEN);
 
-        let rank_idx = quot.as_usize() * self.symbol_with_sentinel_count.as_usize() + symidx as usize;
-        let rank_precount = self.rank_checkpoints[rank_idx];
+        let rank_idx = quot.as_usize() * self.symbol_with_sentinel_count().as_usize() + symidx as usize;
+        let rank_precount = self.rank_checkpoint(rank_idx)?;
         if rem == 0 {
-            rank_precount
+            Ok(rank_precount)
         } else {
-            let rem_count = self.blocks[quot.as_usize()].get_remain_count_of(rem, symidx);
-            rank_precount + P::from_u32(rem_count)
+            let rem_count = self.with_block(quot.as_usize(), |block| block.get_remain_count_of(rem, symidx))?;
+            Ok(rank_precount + P::from_u32(rem_count))
         }
     }
     #[inline]
-    pub fn get_pre_rank_and_symidx(&self, mut pos: P) -> Option<(P, u8)> {
-        if pos == self.sentinel_index - P::ONE {
-            return None;
-        } else if pos < self.sentinel_index {
+    fn get_pre_rank_and_symidx(&self, mut pos: P) -> Result<Option<(P, u8)>, Self::Error> {
+        if pos == self.sentinel_index() - P::ONE {
+            return Ok(None);
+        } else if pos < self.sentinel_index() {
             pos += P::ONE;
         }
-        let (quot, rem) = pos.div_rem_with_u32(B::BLOCK_LEN);
-        
-        let block = &self.blocks[quot.as_usize()];
-        let symidx = block.get_symidx_of(rem);
+        let (quot, rem) = pos.div_rem_with_u32(Self::Block::BLOCK_LEN);
 
-        let rank_idx = quot.as_usize() * self.symbol_with_sentinel_count.as_usize() + symidx as usize;
-        let rank_precount = self.rank_checkpoints[rank_idx];
-        if rem == 0 {
-            Some((rank_precount, symidx))
-        } else {
-            let rem_count = block.get_remain_count_of(rem, symidx);
-            Some((rank_precount + P::from_u32(rem_count), symidx))
-        }
+        let (symidx, rem_count) = self.with_block(quot.as_usize(), |block| {
+            let symidx = block.get_symidx_of(rem);
+            let rem_count = if rem == 0 { 0 } else { block.get_remain_count_of(rem, symidx) };
+            (symidx, rem_count)
+        })?;
+
+        let rank_idx = quot.as_usize() * self.symbol_with_sentinel_count().as_usize() + symidx as usize;
+        let rank_precount = self.rank_checkpoint(rank_idx)?;
+        Ok(Some((rank_precount + P::from_u32(rem_count), symidx)))
+    }
+}
+
+impl<P: Position, B: Block> BwmItems<P> for BwmView<'_, P, B> {
+    type Block = B;
+    type Error = Infallible;
+
+    #[inline]
+    fn symbol_with_sentinel_count(&self) -> P {
+        self.symbol_with_sentinel_count
+    }
+    #[inline]
+    fn sentinel_index(&self) -> P {
+        self.sentinel_index
+    }
+    #[inline]
+    fn rank_checkpoint(&self, rank_idx: usize) -> Result<P, Infallible> {
+        Ok(self.rank_checkpoints[rank_idx])
+    }
+    #[inline]
+    fn with_block<T>(&self, block_idx: usize, f: impl FnOnce(&B) -> T) -> Result<T, Infallible> {
+        Ok(f(&self.blocks[block_idx]))
     }
 }
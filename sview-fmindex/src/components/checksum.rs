@@ -0,0 +1,97 @@
+use super::Header;
+#[cfg(feature = "std")]
+use super::{Toc, SectionKind, PREFIX_SIZE};
+
+/// Checksums (CRC-32) of each header, body and metadata section of the blob
+///
+/// Written at the end of the build, after all other sections.
+/// The checksum of the metadata is zero without the metadata section, as the checksum of no bytes.
+#[repr(C)]
+#[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChecksumHeader {
+    // Headers
+    pub prefix: u32, // magic number, type descriptor & table of contents
+    pub text_encoder: u32,
+    pub count_array_header: u32,
+    pub suffix_array_header: u32,
+    pub bwm_header: u32,
+    // Bodies
+    pub count_array: u32,
+    pub kmer_multiplier: u32,
+    pub kmer_count_table: u32,
+    pub suffix_array: u32,
+    pub sentinel_index: u32,
+    pub rank_checkpoints: u32,
+    pub blocks: u32,
+    // Metadata
+    pub metadata: u32,
+}
+
+impl Header for ChecksumHeader {
+    fn swap_byte_order(&mut self) {
+        self.sections_mut().into_iter().for_each(|(_, checksum)| *checksum = checksum.swap_bytes());
+    }
+}
+
+impl ChecksumHeader {
+    /// Checksum of bytes
+    pub fn checksum_of(bytes: &[u8]) -> u32 {
+        crc32fast::hash(bytes)
+    }
+    /// Checksum of the bytes chained in order
+    pub fn checksum_of_chain(chain: &[&[u8]]) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        chain.iter().for_each(|bytes| hasher.update(bytes));
+        hasher.finalize()
+    }
+    /// Checksums of the sections written in the native blob, at the ranges listed in the TOC
+    ///
+    /// The TOC must be written at the `PREFIX_SIZE` of the blob, and list every required section.
+    #[cfg(feature = "std")]
+    pub fn of_sections(blob: &[u8], toc: &Toc) -> Self {
+        let checksum_of_section = |kind: SectionKind| match toc.entry(kind) {
+            Some(entry) => Self::checksum_of(&blob[entry.offset as usize..entry.end() as usize]),
+            None => panic!("Table of contents must list the {}", kind.name()),
+        };
+        Self {
+            prefix: Self::checksum_of(&blob[..PREFIX_SIZE + toc.raw_size()]),
+            text_encoder: checksum_of_section(SectionKind::TextEncoder),
+            count_array_header: checksum_of_section(SectionKind::CountArrayHeader),
+            suffix_array_header: checksum_of_section(SectionKind::SuffixArrayHeader),
+            bwm_header: checksum_of_section(SectionKind::BwmHeader),
+            count_array: checksum_of_section(SectionKind::CountArray),
+            kmer_multiplier: checksum_of_section(SectionKind::KmerMultiplier),
+            kmer_count_table: checksum_of_section(SectionKind::KmerCountTable),
+            suffix_array: checksum_of_section(SectionKind::SuffixArray),
+            sentinel_index: checksum_of_section(SectionKind::SentinelIndex),
+            rank_checkpoints: checksum_of_section(SectionKind::RankCheckpoints),
+            blocks: checksum_of_section(SectionKind::Blocks),
+            metadata: toc.entry(SectionKind::Metadata)
+                .map_or(0, |entry| Self::checksum_of(&blob[entry.offset as usize..entry.end() as usize])),
+        }
+    }
+    /// Name and checksum of each section
+    pub fn sections(&self) -> [(&'static str, u32); 13] {
+        let mut checksum_header = self.clone();
+        checksum_header.sections_mut().map(|(section, checksum)| (section, *checksum))
+    }
+    /// Name and mutable checksum of each section, in the order of the blob
+    pub fn sections_mut(&mut self) -> [(&'static str, &mut u32); 13] {
+        [
+            ("prefix", &mut self.prefix),
+            ("text encoder", &mut self.text_encoder),
+            ("count array header", &mut self.count_array_header),
+            ("suffix array header", &mut self.suffix_array_header),
+            ("bwm header", &mut self.bwm_header),
+            ("count array", &mut self.count_array),
+            ("k-mer multiplier", &mut self.kmer_multiplier),
+            ("k-mer count table", &mut self.kmer_count_table),
+            ("suffix array", &mut self.suffix_array),
+            ("sentinel index", &mut self.sentinel_index),
+            ("rank checkpoints", &mut self.rank_checkpoints),
+            ("blocks", &mut self.blocks),
+            ("metadata", &mut self.metadata),
+        ]
+    }
+}
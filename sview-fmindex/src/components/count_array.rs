@@ -1,7 +1,14 @@
+use alloc::vec::Vec;
 use zerocopy::IntoBytes;
 
-use crate::Position;
-use super::{TextEncoder, Aligned, Header, View};
+use crate::{Position, LoadError, VerifyError};
+use super::{TextEncoder, Aligned, Header, View, ChecksumHeader, SectionSize, SectionKind, items_size};
+#[cfg(feature = "std")]
+use crate::{BuildError, builder::{BuildMonitor, BuildPhase}};
+#[cfg(all(feature = "std", not(feature = "parallel")))]
+use crate::builder::REPORT_INTERVAL;
+#[cfg(feature = "parallel")]
+use crate::builder::PhaseProgress;
 
 #[repr(C)]
 #[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
@@ -30,30 +37,77 @@ pub struct CountArrayView<'a, P: Position> {
 
 impl CountArrayHeader {
     fn count_array_raw_size<P: Position>(&self) -> usize {
-        self.count_array_len as usize * std::mem::size_of::<P>()
+        items_size(self.count_array_len as u64, core::mem::size_of::<P>())
     }
     fn count_array_aligned_size<P: Position, A: Aligned>(&self) -> usize {
         A::aligned_size(self.count_array_raw_size::<P>())
     }
     fn kmer_multiplier_raw_size(&self) -> usize {
-        self.kmer_multiplier_len as usize * std::mem::size_of::<usize>()
+        items_size(self.kmer_multiplier_len as u64, core::mem::size_of::<u64>())
     }
     fn kmer_multiplier_aligned_size<A: Aligned>(&self) -> usize {
         A::aligned_size(self.kmer_multiplier_raw_size())
     }
     fn kmer_count_table_raw_size<P: Position>(&self) -> usize {
-        self.kmer_count_table_len as usize * std::mem::size_of::<P>()
+        items_size(self.kmer_count_table_len, core::mem::size_of::<P>())
     }
     fn kmer_count_table_aligned_size<P: Position, A: Aligned>(&self) -> usize {
         A::aligned_size(self.kmer_count_table_raw_size::<P>())
     }
+    pub fn body_sections<P: Position, A: Aligned>(&self) -> [SectionSize; 3] {
+        [
+            SectionSize {
+                kind: SectionKind::CountArray,
+                raw_size: self.count_array_raw_size::<P>(),
+                aligned_size: self.count_array_aligned_size::<P, A>(),
+                item_size: core::mem::size_of::<P>(),
+            },
+            SectionSize {
+                kind: SectionKind::KmerMultiplier,
+                raw_size: self.kmer_multiplier_raw_size(),
+                aligned_size: self.kmer_multiplier_aligned_size::<A>(),
+                item_size: core::mem::size_of::<u64>(),
+            },
+            SectionSize {
+                kind: SectionKind::KmerCountTable,
+                raw_size: self.kmer_count_table_raw_size::<P>(),
+                aligned_size: self.kmer_count_table_aligned_size::<P, A>(),
+                item_size: core::mem::size_of::<P>(),
+            },
+        ]
+    }
 }
 
-impl Header for CountArrayHeader {}
+impl Header for CountArrayHeader {
+    fn swap_byte_order(&mut self) {
+        self.symbol_count = self.symbol_count.swap_bytes();
+        self.lookup_table_kmer_size = self.lookup_table_kmer_size.swap_bytes();
+        self.count_array_len = self.count_array_len.swap_bytes();
+        self.kmer_multiplier_len = self.kmer_multiplier_len.swap_bytes();
+        self.kmer_count_table_len = self.kmer_count_table_len.swap_bytes();
+    }
+}
+
+// ================================================
+// Validate
+// ================================================
+impl CountArrayHeader {
+    /// Check that the lengths in header are consistent
+    pub fn is_valid(&self, max_symbol: u32) -> bool {
+        let kmer_count_table_len = (self.symbol_count as u64 + 1).checked_pow(self.lookup_table_kmer_size);
+
+        self.symbol_count <= max_symbol
+        && self.count_array_len == self.symbol_count + 1
+        && self.lookup_table_kmer_size >= 1
+        && self.kmer_multiplier_len == self.lookup_table_kmer_size
+        && kmer_count_table_len == Some(self.kmer_count_table_len)
+    }
+}
 
 // ================================================
 // Build
 // ================================================
+#[cfg(feature = "std")]
 impl CountArrayHeader {
     pub fn new(
         symbol_count: u32,
@@ -80,20 +134,21 @@ impl CountArrayHeader {
         text: &mut Vec<u8>,
         text_encoder: &E,
         blob: &mut [u8],
-    ) {
+        monitor: &BuildMonitor,
+    ) -> Result<(), BuildError> {
         // 1) Init
         let symbol_with_sentinel_count = self.count_array_len as usize;
         //  - count array
         let mut count_array = vec![P::ZERO; self.count_array_len as usize];
         //  - kmer multiplier (+ 빠른 위치 검색을 위한 sym 인덱스 계산)
-        let kmer_multiplier: Vec<usize> = {
+        let kmer_multiplier: Vec<u64> = {
             (0..self.lookup_table_kmer_size).map(|pos| {
-                (symbol_with_sentinel_count).pow(pos)
+                (symbol_with_sentinel_count as u64).pow(pos)
             }).rev().collect()
         };
         let index_for_each_symbol: Vec<usize> = {
             (0..(self.symbol_count) as usize).map(|symidx| {
-                kmer_multiplier[0] * (symidx + 1)
+                kmer_multiplier[0] as usize * (symidx + 1)
             }).collect()
         };
         // - kmer count array
@@ -108,19 +163,37 @@ impl CountArrayHeader {
         };
         
         // 2) Counting
-        let mut table_index: usize = 0;
-        text.iter_mut().rev().for_each(|sym| {
-            let symidx = text_encoder.idx_of(*sym);
-            // Transform sym to symidx + 1 (sentinel will be 0 for sorting)
-            *sym = symidx + 1;
-            // Add count to counts
-            count_array[symidx as usize + 1] += P::ONE;
-            // Update table_index for kmer_count_array
-            table_index /= symbol_with_sentinel_count;
-            table_index += index_for_each_symbol[symidx as usize];
-            // Add count to lookup table
-            kmer_count_array[table_index] += P::ONE;
-        });
+        let progress = monitor.start(BuildPhase::Counting, text.len() as u64)?;
+        #[cfg(not(feature = "parallel"))]
+        {
+            let mut table_index: usize = 0;
+            for chunk in text.rchunks_mut(REPORT_INTERVAL) {
+                chunk.iter_mut().rev().for_each(|sym| {
+                    let symidx = text_encoder.idx_of(*sym);
+                    // Transform sym to symidx + 1 (sentinel will be 0 for sorting)
+                    *sym = symidx + 1;
+                    // Add count to counts
+                    count_array[symidx as usize + 1] += P::ONE;
+                    // Update table_index for kmer_count_array
+                    table_index /= symbol_with_sentinel_count;
+                    table_index += index_for_each_symbol[symidx as usize];
+                    // Add count to lookup table
+                    kmer_count_array[table_index] += P::ONE;
+                });
+                progress.add(chunk.len() as u64)?;
+            }
+        }
+        #[cfg(feature = "parallel")]
+        count_in_parallel(
+            text,
+            text_encoder,
+            self.lookup_table_kmer_size as usize,
+            &index_for_each_symbol,
+            &mut count_array,
+            kmer_count_array,
+            &progress,
+        )?;
+        progress.finish()?;
 
         accumulate_count_array(&mut count_array);
         accumulate_count_array(&mut kmer_count_array);
@@ -132,10 +205,93 @@ impl CountArrayHeader {
         blob[
             self.count_array_aligned_size::<P, A>()
             ..self.count_array_aligned_size::<P, A>() + self.kmer_multiplier_raw_size()
-        ].copy_from_slice(kmer_multiplier.as_bytes());
+        ].copy_from_slice(&kmer_multiplier_to_bytes(kmer_multiplier.iter().copied()));
+        Ok(())
     }
 }
 
+// The k-mer multiplier is always stored as `u64`, regardless of the pointer width
+fn kmer_multiplier_to_bytes(kmer_multiplier: impl Iterator<Item = u64>) -> Vec<u8> {
+    kmer_multiplier.flat_map(u64::to_ne_bytes).collect()
+}
+
+// Encode the chunks of text, then count the k-mers of the chunks from the encoded text
+//  - The k-mer index of the last position of each chunk is rolled from the next chunk.
+//  - The k-mers are counted into the tables of each thread, only if the tables are smaller than the text.
+//    Otherwise, the chunks are counted in order into the shared table.
+#[cfg(feature = "parallel")]
+fn count_in_parallel<P: Position, E: TextEncoder>(
+    text: &mut [u8],
+    text_encoder: &E,
+    kmer_size: usize,
+    index_for_each_symbol: &[usize],
+    count_array: &mut [P],
+    kmer_count_array: &mut [P],
+    progress: &PhaseProgress,
+) -> Result<(), BuildError> {
+    use rayon::prelude::*;
+
+    let symbol_with_sentinel_count = count_array.len();
+    let chunk_len = (text.len() / (rayon::current_num_threads() * 4)).max(1 << 16);
+    // The text is passed twice, so each pass reports a half of the chunk as processed
+    let encoding_share = |len: usize| (len / 2) as u64;
+    let counting_share = |len: usize| (len - len / 2) as u64;
+    let add_counts = |mut counts: Vec<P>, other: Vec<P>| {
+        counts.iter_mut().zip(other).for_each(|(count, other_count)| *count += other_count);
+        counts
+    };
+
+    // Encode & count the symbols
+    let symbol_counts = text.par_chunks_mut(chunk_len).map(|chunk| {
+        let mut symbol_counts = vec![P::ZERO; symbol_with_sentinel_count];
+        chunk.iter_mut().for_each(|sym| {
+            let symidx = text_encoder.idx_of(*sym);
+            // Transform sym to symidx + 1 (sentinel will be 0 for sorting)
+            *sym = symidx + 1;
+            symbol_counts[symidx as usize + 1] += P::ONE;
+        });
+        progress.add(encoding_share(chunk.len()))?;
+        Ok::<_, BuildError>(symbol_counts)
+    }).try_reduce(|| vec![P::ZERO; symbol_with_sentinel_count], |counts, other| Ok(add_counts(counts, other)))?;
+    count_array.copy_from_slice(&symbol_counts);
+
+    // Count the k-mers
+    let text: &[u8] = text;
+    let count_kmers = |start: usize, end: usize, kmer_count_array: &mut [P]| {
+        let mut table_index: usize = 0;
+        let roll = |symidx_with_sentinel: u8, table_index: &mut usize| {
+            *table_index /= symbol_with_sentinel_count;
+            *table_index += index_for_each_symbol[symidx_with_sentinel as usize - 1];
+        };
+        text[end..(end + kmer_size).min(text.len())].iter().rev().for_each(|sym| roll(*sym, &mut table_index));
+        text[start..end].iter().rev().for_each(|sym| {
+            roll(*sym, &mut table_index);
+            kmer_count_array[table_index] += P::ONE;
+        });
+    };
+    let chunk_starts = (0..text.len()).step_by(chunk_len);
+    if kmer_count_array.len() * rayon::current_num_threads() <= text.len() {
+        let kmer_counts = chunk_starts.collect::<Vec<_>>().into_par_iter().try_fold(
+            || vec![P::ZERO; kmer_count_array.len()],
+            |mut kmer_counts, start| {
+                let end = (start + chunk_len).min(text.len());
+                count_kmers(start, end, &mut kmer_counts);
+                progress.add(counting_share(end - start))?;
+                Ok::<_, BuildError>(kmer_counts)
+            },
+        ).try_reduce(|| vec![P::ZERO; kmer_count_array.len()], |counts, other| Ok(add_counts(counts, other)))?;
+        kmer_count_array.copy_from_slice(&kmer_counts);
+    } else {
+        for start in chunk_starts {
+            let end = (start + chunk_len).min(text.len());
+            count_kmers(start, end, kmer_count_array);
+            progress.add(counting_share(end - start))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
 fn accumulate_count_array<P: Position>(count_array: &mut [P]) {
     let mut accumulated_count = P::ZERO;
     count_array.iter_mut().for_each(|count| {
@@ -160,35 +316,103 @@ impl<'a, P: Position> View<'a> for CountArrayView<'a, P> {
     fn load_from_body<A: Aligned>(
         header: &Self::Header,
         body_blob: &'a [u8],
-    ) -> Self {
+    ) -> Result<Self, LoadError> {
         let mut body_start_index = 0;
         let mut body_end_index = header.count_array_raw_size::<P>();
         let mut next_body_start_index = header.count_array_aligned_size::<P, A>();
 
         // Count array
-        let count_array_bytes = &body_blob[body_start_index..body_end_index];
-        let count_array: &[P] = zerocopy::FromBytes::ref_from_bytes(count_array_bytes).unwrap();
+        let count_array: &[P] = body_blob.get(body_start_index..body_end_index)
+            .and_then(|bytes| zerocopy::FromBytes::ref_from_bytes(bytes).ok())
+            .ok_or(LoadError::InvalidSection("count array"))?;
 
         // Kmer multiplier
         body_start_index = next_body_start_index;
         body_end_index = body_start_index + header.kmer_multiplier_raw_size();
         next_body_start_index = body_start_index + header.kmer_multiplier_aligned_size::<A>();
-        let kmer_multiplier_bytes = &body_blob[body_start_index..body_end_index];
-        let kmer_multiplier: &[usize] = zerocopy::FromBytes::ref_from_bytes(kmer_multiplier_bytes).unwrap();
+        //  - copied, so it is read regardless of the alignment and the pointer width
+        let kmer_multiplier: Vec<usize> = body_blob.get(body_start_index..body_end_index)
+            .and_then(|bytes| {
+                bytes.chunks_exact(core::mem::size_of::<u64>()).map(|chunk| {
+                    let mul_of_pos = u64::from_ne_bytes(chunk.try_into().unwrap());
+                    usize::try_from(mul_of_pos).ok()
+                }).collect()
+            })
+            .ok_or(LoadError::InvalidSection("k-mer multiplier"))?;
 
         // Kmer count table
         body_start_index = next_body_start_index;
         body_end_index = body_start_index + header.kmer_count_table_raw_size::<P>();
-        let kmer_count_table_bytes = &body_blob[body_start_index..body_end_index];
-        let kmer_count_table: &'a [P] = zerocopy::FromBytes::ref_from_bytes(kmer_count_table_bytes).unwrap();
+        let kmer_count_table: &'a [P] = body_blob.get(body_start_index..body_end_index)
+            .and_then(|bytes| zerocopy::FromBytes::ref_from_bytes(bytes).ok())
+            .ok_or(LoadError::InvalidSection("k-mer count table"))?;
         
-        Self {
+        Ok(Self {
             lookup_table_kmer_size: header.lookup_table_kmer_size as usize,
             count_array: count_array.to_vec(),
-            kmer_multiplier: kmer_multiplier.to_vec(),
+            kmer_multiplier,
             kmer_count_table,
+        })
+    }
+}
+
+// ================================================
+// Checksum
+// ================================================
+impl<P: Position> CountArrayView<'_, P> {
+    pub fn write_checksums(&self, checksum_header: &mut ChecksumHeader) {
+        checksum_header.count_array = ChecksumHeader::checksum_of(self.count_array.as_bytes());
+        checksum_header.kmer_multiplier = ChecksumHeader::checksum_of(
+            &kmer_multiplier_to_bytes(self.kmer_multiplier.iter().map(|&mul_of_pos| mul_of_pos as u64)),
+        );
+        checksum_header.kmer_count_table = ChecksumHeader::checksum_of(self.kmer_count_table.as_bytes());
+    }
+}
+
+// ================================================
+// Validate
+// ================================================
+impl<P: Position> CountArrayView<'_, P> {
+    /// Validate the counts and return the text length
+    pub fn validate_structure(&self, symbol_count: u32) -> Result<P, VerifyError> {
+        // Count array
+        if self.count_array.first() != Some(&P::ZERO) || !is_monotone(&self.count_array) {
+            return Err(VerifyError::InvalidStructure {
+                section: "count array",
+                reason: "is not monotone",
+            });
+        }
+        let text_len = *self.count_array.last().unwrap();
+
+        // Kmer multiplier
+        let symbol_with_sentinel_count = symbol_count as u64 + 1;
+        let is_valid_kmer_multiplier = self.kmer_multiplier.iter().rev().enumerate().all(|(pos, &mul_of_pos)| {
+            symbol_with_sentinel_count.checked_pow(pos as u32) == Some(mul_of_pos as u64)
+        });
+        if !is_valid_kmer_multiplier {
+            return Err(VerifyError::InvalidStructure {
+                section: "k-mer multiplier",
+                reason: "is not the powers of the symbol count",
+            });
         }
+
+        // Kmer count table
+        if !is_monotone(self.kmer_count_table) || self.kmer_count_table.last() != Some(&text_len) {
+            return Err(VerifyError::InvalidStructure {
+                section: "k-mer count table",
+                reason: "is not monotone up to the text length",
+            });
+        }
+
+        Ok(text_len)
     }
+    pub fn symbol_count_of(&self, symidx: usize) -> P {
+        self.count_array[symidx + 1] - self.count_array[symidx]
+    }
+}
+
+fn is_monotone<P: Position>(counts: &[P]) -> bool {
+    counts.windows(2).all(|window| window[0] <= window[1])
 }
 
 // ================================================
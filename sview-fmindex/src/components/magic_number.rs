@@ -3,14 +3,70 @@ use super::Header;
 const MAGIC_NUMBER_HEADER_1: u8 = b'F'; // Fm
 const MAGIC_NUMBER_HEADER_2: u8 = b'I'; // Index
 const MAGIC_NUMBER_VERSION_1: u8 = b'0'; // Major Version
-const MAGIC_NUMBER_VERSION_2: u8 = b'0'; // Minor Version
+const MAGIC_NUMBER_VERSION_2: u8 = b'1'; // Minor Version
+const MAGIC_NUMBER_OLDEST_VERSION_2: u8 = b'0'; // Oldest minor version that can be read
+const BYTE_ORDER_LITTLE_ENDIAN: u8 = b'L';
+const BYTE_ORDER_BIG_ENDIAN: u8 = b'B';
 
-/// Magic number for FM-index (Little-endian)
+/// Magic number for FM-index
+///
+/// The layouts of the format versions that can be read:
+/// - 0.0: the magic number is aligned by the block, and the headers and bodies follow it contiguously.
+///   The types, byte order and checksums are not recorded, and the blob is read as little-endian.
+/// - 0.1: the type descriptor, table of contents and checksum header follow the magic number.
+///
+/// Every integer in the blob is fixed-width and stored in the byte order of the target it was built on.
+/// The byte order is recorded right after the version, so that a blob is never read in the other byte order.
 #[repr(C)]
 #[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MagicNumber([u8; 8]);
 
+/// Byte order of the integers in blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    LittleEndian,
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// Byte order of the current target
+    pub const NATIVE: Self = if cfg!(target_endian = "big") {
+        Self::BigEndian
+    } else {
+        Self::LittleEndian
+    };
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::LittleEndian => Self::BigEndian,
+            Self::BigEndian => Self::LittleEndian,
+        }
+    }
+    const fn marker(self) -> u8 {
+        match self {
+            Self::LittleEndian => BYTE_ORDER_LITTLE_ENDIAN,
+            Self::BigEndian => BYTE_ORDER_BIG_ENDIAN,
+        }
+    }
+    fn from_marker(marker: u8) -> Option<Self> {
+        match marker {
+            BYTE_ORDER_LITTLE_ENDIAN => Some(Self::LittleEndian),
+            BYTE_ORDER_BIG_ENDIAN => Some(Self::BigEndian),
+            _ => None,
+        }
+    }
+}
+
+impl core::fmt::Display for ByteOrder {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::LittleEndian => write!(f, "little-endian"),
+            Self::BigEndian => write!(f, "big-endian"),
+        }
+    }
+}
+
 impl MagicNumber {
     pub fn new() -> Self {
         Self([
@@ -18,11 +74,11 @@ impl MagicNumber {
             MAGIC_NUMBER_HEADER_2,
             MAGIC_NUMBER_VERSION_1,
             MAGIC_NUMBER_VERSION_2,
+            ByteOrder::NATIVE.marker(),
             // Padding
             0,
             0,
             0,
-            0,
         ])
     }
 
@@ -33,18 +89,70 @@ impl MagicNumber {
     pub fn minor_version(&self) -> u8 {
         self.0[3]
     }
+    /// Version in numbers (major, minor)
+    pub fn version(&self) -> (u8, u8) {
+        (
+            self.major_version().wrapping_sub(b'0'),
+            self.minor_version().wrapping_sub(b'0'),
+        )
+    }
+    /// Returns `None` if the marker is unknown
+    pub fn byte_order(&self) -> Option<ByteOrder> {
+        if self.is_untyped_version() {
+            Some(ByteOrder::LittleEndian)
+        } else {
+            ByteOrder::from_marker(self.0[4])
+        }
+    }
+    // Setters
+    pub fn set_byte_order(&mut self, byte_order: ByteOrder) {
+        self.0[4] = byte_order.marker();
+    }
     // Checkers
     /// Check if the magic number has valid header
     pub fn is_valid(&self) -> bool {
         self.0[0] == MAGIC_NUMBER_HEADER_1
         && self.0[1] == MAGIC_NUMBER_HEADER_2
+        && self.byte_order().is_some()
     }
     /// Check if supported
-    /// ! Currently, only the same version is supported
+    /// The previous minor versions of the same major version are supported
     pub fn is_supported_version(&self) -> bool {
+        self.major_version() == MAGIC_NUMBER_VERSION_1
+        && (MAGIC_NUMBER_OLDEST_VERSION_2..=MAGIC_NUMBER_VERSION_2).contains(&self.minor_version())
+    }
+    /// Check if the blob has the layout of the version 0.0, without the types
+    pub fn is_untyped_version(&self) -> bool {
+        self.minor_version() == MAGIC_NUMBER_OLDEST_VERSION_2
+    }
+    /// Check if the blob has the layout of the current version
+    pub fn is_current_version(&self) -> bool {
         self.major_version() == MAGIC_NUMBER_VERSION_1
         && self.minor_version() == MAGIC_NUMBER_VERSION_2
     }
+    /// Check the magic number at the start of the blob in the const context
+    ///
+    /// The message tells why the blob can not be loaded on the current target.
+    pub const fn check_prefix(blob: &[u8]) -> Result<(), &'static str> {
+        if blob.len() < 8 {
+            return Err("The blob is shorter than the magic number.");
+        }
+        if blob[0] != MAGIC_NUMBER_HEADER_1 || blob[1] != MAGIC_NUMBER_HEADER_2 {
+            return Err("The blob does not start with the magic number of fm-index.");
+        }
+        if blob[2] != MAGIC_NUMBER_VERSION_1
+            || blob[3] < MAGIC_NUMBER_OLDEST_VERSION_2
+            || blob[3] > MAGIC_NUMBER_VERSION_2
+        {
+            return Err("The format version of the blob is not supported.");
+        }
+        // The byte order is not recorded in the version 0.0
+        let byte_order_marker = if blob[3] == MAGIC_NUMBER_OLDEST_VERSION_2 { BYTE_ORDER_LITTLE_ENDIAN } else { blob[4] };
+        if byte_order_marker != ByteOrder::NATIVE.marker() {
+            return Err("The byte order of the blob differs from the target. Convert it with `convert_endianness`.");
+        }
+        Ok(())
+    }
 }
 
-impl Header for MagicNumber {}
\ No newline at end of file
+impl Header for MagicNumber {}
@@ -1,12 +1,42 @@
+use crate::LoadError;
+
 pub trait Aligned {
     const ALIGN_SIZE: usize;
 
     fn aligned_size(raw_size: usize) -> usize {
         let rem = raw_size % Self::ALIGN_SIZE;
-        if rem == 0 { raw_size } else { raw_size + (Self::ALIGN_SIZE - rem) }
+        if rem == 0 { raw_size } else { raw_size.saturating_add(Self::ALIGN_SIZE - rem) }
     }
 }
 
+/// Size of a section in blob
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionSize {
+    pub kind: SectionKind,
+    pub raw_size: usize,
+    pub aligned_size: usize,
+    /// Size of each integer in the section
+    pub item_size: usize,
+}
+
+/// Size in bytes of `len` items
+///
+/// Saturated at `usize::MAX`, so that the lengths from corrupted headers never overflow.
+pub fn items_size(len: u64, item_size: usize) -> usize {
+    usize::try_from(len).unwrap_or(usize::MAX).saturating_mul(item_size)
+}
+
+/// Alignment of the blob prefix (magic number and type descriptor).
+///
+/// The prefix is read before the block type is known,
+/// so it does not depend on the alignment of the block.
+/// The prefix is 16 bytes, which keeps the following headers aligned for every block.
+pub struct PrefixAlign;
+
+impl Aligned for PrefixAlign {
+    const ALIGN_SIZE: usize = 8;
+}
+
 pub trait Header: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutable + zerocopy::KnownLayout + Sized {
     fn aligned_size<A: Aligned>(&self) -> usize {
         let raw_size = self.as_bytes().len();
@@ -15,31 +45,46 @@ pub trait Header: zerocopy::FromBytes + zerocopy::IntoBytes + zerocopy::Immutabl
     fn write_to_blob(&self, blob: &mut [u8]) {
         self.write_to_prefix(blob).unwrap();
     }
-    fn read_from_blob<'a, A: Aligned>(blob: &'a [u8]) -> (Self, &'a [u8]) {
-        let (header, _) = Self::read_from_prefix(blob).unwrap();
-        let remaining_bytes = &blob[header.aligned_size::<A>()..];
-        (header, remaining_bytes)
+    /// Reverse the byte order of each integer field
+    ///
+    /// Headers with only `u8` fields do not have to implement this.
+    fn swap_byte_order(&mut self) {}
+    /// Returns `None` if the blob is shorter than the aligned header
+    fn read_from_blob<'a, A: Aligned>(blob: &'a [u8]) -> Option<(Self, &'a [u8])> {
+        let (header, _) = Self::read_from_prefix(blob).ok()?;
+        let remaining_bytes = blob.get(header.aligned_size::<A>()..)?;
+        Some((header, remaining_bytes))
     }
 }
 
-pub trait View<'a> {
+pub trait View<'a>: Sized {
     type Header;
 
+    // Used by the builder
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
     fn aligned_body_size<A: Aligned>(header: &Self::Header) -> usize;
     fn load_from_body<A: Aligned>(
         header: &Self::Header,
         body_blob: &'a [u8],
-    ) -> Self;
+    ) -> Result<Self, LoadError>;
 }
 
 mod magic_number;
+mod type_descriptor;
 mod text_encoder;
 mod count_array;
 mod suffix_array;
 mod bwm;
+mod checksum;
+mod toc;
 
-pub use magic_number::MagicNumber;
+pub use magic_number::{MagicNumber, ByteOrder};
+pub use type_descriptor::TypeDescriptor;
+pub(crate) use type_descriptor::match_type_descriptor;
 pub use text_encoder::{text_encoders, TextEncoder};
 pub use count_array::{CountArrayHeader, CountArrayView};
-pub use suffix_array::{SuffixArrayHeader, SuffixArrayView};
-pub use bwm::{BwmHeader, BwmView, Block, blocks};
\ No newline at end of file
+pub use suffix_array::{SuffixArrayHeader, SuffixArrayView, SuffixArrayItems};
+pub use bwm::{BwmHeader, BwmView, BwmItems, Block, blocks};
+pub use checksum::ChecksumHeader;
+pub use toc::{Toc, SectionKind, PREFIX_SIZE};
+pub(crate) use toc::header_section;
\ No newline at end of file
@@ -6,10 +6,10 @@ const SENTINEL_SYMBOL: u8 = 0;
 
 #[inline]
 pub fn get_compressed_suffix_array_and_pidx_while_bwt_with_crate_bio<P: Position>(text: &mut Vec<u8>, sampling_ratio: P) -> (Vec<P>, P) {
-    let mut input_string = text.to_vec(); // FIXME: to_vec is needed?
-    input_string.push(SENTINEL_SYMBOL);
-    let mut suffix_array = get_suffix_array(&input_string);
-    let mut bwt = get_bwt(&input_string, &suffix_array);
+    // The sentinel is appended to the text itself, in the capacity reserved by the builder.
+    text.push(SENTINEL_SYMBOL);
+    let mut suffix_array = get_suffix_array(text);
+    let mut bwt = get_bwt(text, &suffix_array);
     
     let pidx = get_pidx_from_bwt(&bwt);
 
@@ -0,0 +1,268 @@
+use std::io::{self, Read, Write};
+use std::path::Path;
+
+use crate::{Position, BuildError};
+use crate::builder::{BuildMonitor, BuildPhase, REPORT_INTERVAL};
+
+mod sorter;
+use sorter::{Record, ScratchDir, ScratchFile, ExternalSorter};
+
+const SENTINEL_SYMBOL: u8 = 0;
+// The rank and the flag of uniqueness are packed in a `u64`.
+const MAX_TEXT_LEN: usize = 1 << 56;
+
+/// Suffix array by prefix doubling with discarding, over the sorts in external memory
+///
+/// The ranks of the suffixes are refined by the ranks of the prefixes of `2h` symbols,
+/// from the ranks of the prefixes of `h` symbols, until every rank is unique.
+/// The first ranks are the packed k-mers, so that the doubling starts from `h = k`.
+/// A suffix of unique rank is discarded from the next rounds,
+/// unless its rank is still needed to refine the suffix `2h` before.
+/// The ranks, sorted records and the outputs of each round are written to the scratch files in `temp_dir`,
+/// and the records in memory are kept within `memory_budget` bytes.
+/// Only the text stays in memory, and it is replaced with the BWT at the end.
+/// The sort is reported by the suffixes of which the ranks are final,
+/// which only grow over the rounds, as the number of rounds is not known in advance.
+pub fn get_compressed_suffix_array_and_pidx_while_bwt_in_external_memory<P: Position>(
+    text: &mut Vec<u8>,
+    sampling_ratio: P,
+    temp_dir: &Path,
+    memory_budget: usize,
+    monitor: &BuildMonitor,
+) -> Result<(Vec<P>, P), BuildError> {
+    sort_in_external_memory(text, sampling_ratio, temp_dir, memory_budget, monitor).map(|(result, _)| result)
+}
+
+/// Work done by the sort in external memory
+#[derive(Debug, Default)]
+pub(super) struct SortStats {
+    /// Rounds of the doubling
+    pub rounds: usize,
+    /// Records sorted by the pairs of ranks, in all rounds
+    pub sorted_pairs: u64,
+}
+
+pub(super) fn sort_in_external_memory<P: Position>(
+    text: &mut Vec<u8>,
+    sampling_ratio: P,
+    temp_dir: &Path,
+    memory_budget: usize,
+    monitor: &BuildMonitor,
+) -> Result<((Vec<P>, P), SortStats), BuildError> {
+    if text.len() >= MAX_TEXT_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "text is too long to be sorted in external memory").into());
+    }
+    text.push(SENTINEL_SYMBOL);
+    let n = text.len();
+    let sampling_ratio = sampling_ratio.as_usize();
+    // Three sorters and the runs of one sorted output are alive at once, in each round.
+    let sort_budget = memory_budget / 4;
+    let scratch_dir = ScratchDir::create_in(temp_dir)?;
+    let mut stats = SortStats::default();
+
+    // Suffixes of which the ranks are final, to be sorted by the ranks at the end
+    let mut finished_sorter = ExternalSorter::new(&scratch_dir, sort_budget, n);
+    let mut finished_count = 0;
+    let progress = monitor.start(BuildPhase::SuffixSorting, n as u64)?;
+
+    // 1) Pairs of the ranks of the k-mers
+    let mut step = kmer_size(text);
+    let mut pair_sorter = ExternalSorter::new(&scratch_dir, sort_budget, n);
+    {
+        let rank_file = write_kmer_ranks(text, &scratch_dir)?;
+        let mut ranks = RankReader(rank_file.reader_at(0)?);
+        let mut next_ranks = RankReader(rank_file.reader_at((step.min(n) * size_of::<u64>()) as u64)?);
+        for position in 0..n {
+            let rank = ranks.read()?;
+            let next_rank = if position + step < n { next_ranks.read()? } else { 0 };
+            pair_sorter.push(Record { key: (rank, next_rank), value: position as u64 })?;
+            if (position + 1) % REPORT_INTERVAL == 0 {
+                progress.check_cancelled()?;
+            }
+        }
+    }
+    let mut pair_count = n;
+    let mut named_sorter = ExternalSorter::new(&scratch_dir, sort_budget, n);
+    let mut is_first_round = true;
+
+    loop {
+        stats.rounds += 1;
+        stats.sorted_pairs += pair_count as u64;
+
+        // 2) Rank the paired suffixes by the first row of the same pair
+        //  - In the first round, all suffixes are paired, and the rows start from the first.
+        //  - In the next rounds, all suffixes of a non-unique rank are paired,
+        //    so that the rows of the same rank start from the rank.
+        let next_step = step.saturating_mul(2);
+        {
+            let mut previous_key = None;
+            let mut rank = 0;
+            let mut index_in_group = 0;
+            // The last suffix, of which the uniqueness depends on the next one
+            let mut pending: Option<(u64, u64, bool)> = None;
+            for record in pair_sorter.finish()? {
+                let record = record?;
+                let is_same_key = previous_key == Some(record.key);
+                if !is_first_round && previous_key.map(|key: (u64, u64)| key.0) != Some(record.key.0) {
+                    index_in_group = 0;
+                }
+                if !is_same_key {
+                    rank = if is_first_round { 1 } else { record.key.0 } + index_in_group;
+                }
+                index_in_group += 1;
+                if let Some((position, rank, is_unique)) = pending {
+                    named_sorter.push(named_record(position, rank, is_unique && !is_same_key, next_step))?;
+                }
+                pending = Some((record.value, rank, !is_same_key));
+                previous_key = Some(record.key);
+            }
+            if let Some((position, rank, is_unique)) = pending {
+                named_sorter.push(named_record(position, rank, is_unique, next_step))?;
+            }
+        }
+        step = next_step;
+        is_first_round = false;
+
+        // 3) Pair the suffixes of non-unique ranks with the suffixes `step` after,
+        //    which are adjacent in the order of (position % step, position / step).
+        //  - The suffixes of unique ranks are kept for the next round,
+        //    only if the suffixes `step` and `2 * step` before are not unique,
+        //    as the suffix `2 * step` before may need the rank in the next round.
+        let next_step = step.saturating_mul(2);
+        let mut named_records = named_sorter.finish()?;
+        pair_sorter = ExternalSorter::new(&scratch_dir, sort_budget, n);
+        named_sorter = ExternalSorter::new(&scratch_dir, sort_budget, n);
+        pair_count = 0;
+        // The positions of the last two suffixes, and whether their ranks are not unique
+        let mut previous: [Option<(u64, bool)>; 2] = [None; 2];
+        let mut next = named_records.next().transpose()?;
+        while let Some(record) = next {
+            next = named_records.next().transpose()?;
+            let (position, rank, is_unique) = unpack_named_record(&record, step);
+            if is_unique {
+                let is_needed = matches!(
+                    previous,
+                    [Some((position_1, true)), Some((position_2, true))]
+                    if position_1 + step as u64 == position && position_2 + 2 * step as u64 == position
+                );
+                if is_needed {
+                    named_sorter.push(named_record(position, rank, true, next_step))?;
+                } else {
+                    finished_sorter.push(Record { key: (rank, 0), value: position })?;
+                    finished_count += 1;
+                    if finished_count % REPORT_INTERVAL == 0 {
+                        progress.set(finished_count as u64)?;
+                    }
+                }
+            } else {
+                // A suffix of non-unique rank ends before the sentinel, with the prefix of `step` symbols.
+                let next_rank = match &next {
+                    Some(next_record) if unpack_named_record(next_record, step).0 == position + step as u64 => {
+                        unpack_named_record(next_record, step).1
+                    },
+                    _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "missing suffix to pair in external memory").into()),
+                };
+                pair_sorter.push(Record { key: (rank, next_rank), value: position })?;
+                pair_count += 1;
+                if pair_count % REPORT_INTERVAL == 0 {
+                    progress.check_cancelled()?;
+                }
+            }
+            previous = [Some((position, !is_unique)), previous[0]];
+        }
+
+        if pair_count == 0 {
+            break;
+        }
+        progress.set(finished_count as u64)?;
+    }
+    progress.finish()?;
+
+    // 4) BWT and sampled suffix array in the order of the final ranks
+    let progress = monitor.start(BuildPhase::Bwt, n as u64)?;
+    let bwt_file = scratch_dir.create_file()?;
+    let mut bwt_writer = bwt_file.writer();
+    let mut compressed_suffix_array = Vec::with_capacity((n - 1).div_ceil(sampling_ratio));
+    let mut pidx = 0;
+    for (row, record) in finished_sorter.finish()?.enumerate() {
+        let position = record?.value as usize;
+        if position == 0 {
+            pidx = row;
+        } else {
+            bwt_writer.write_all(&[text[position - 1]])?;
+        }
+        // The first row is the suffix of the sentinel, which is not sampled.
+        if row != 0 && (row - 1) % sampling_ratio == 0 {
+            compressed_suffix_array.push(P::from_usize(position));
+        }
+        if (row + 1) % REPORT_INTERVAL == 0 {
+            progress.set((row + 1) as u64)?;
+        }
+    }
+    bwt_writer.flush()?;
+    drop(bwt_writer);
+    // Change original text to bwt
+    text.clear();
+    bwt_file.reader_at(0)?.read_to_end(text)?;
+    progress.finish()?;
+
+    Ok(((compressed_suffix_array, P::from_usize(pidx)), stats))
+}
+
+// Records are sorted by (position % step, position / step),
+// so that the suffixes `step` apart are adjacent.
+fn named_record(position: u64, rank: u64, is_unique: bool, step: usize) -> Record {
+    let step = step as u64;
+    Record {
+        key: (position % step, position / step),
+        value: (rank << 1) | is_unique as u64,
+    }
+}
+fn unpack_named_record(record: &Record, step: usize) -> (u64, u64, bool) {
+    let position = record.key.0 + record.key.1 * step as u64;
+    (position, record.value >> 1, record.value & 1 == 1)
+}
+
+// The largest k, of which the k-mers packed in base of (max symbol + 1) fit in `u64`
+fn kmer_size(text: &[u8]) -> usize {
+    let base = text.iter().max().map_or(1, |max_symbol| *max_symbol as u64 + 1);
+    if base < 2 {
+        return 1;
+    }
+    let mut kmer_size = 1;
+    let mut max_kmer = base;
+    while let Some(next_max_kmer) = max_kmer.checked_mul(base) {
+        max_kmer = next_max_kmer;
+        kmer_size += 1;
+    }
+    kmer_size
+}
+
+// The rank of a suffix is its packed k-mer + 1, padded with zero after the end.
+fn write_kmer_ranks(text: &[u8], scratch_dir: &ScratchDir) -> io::Result<ScratchFile> {
+    let base = text.iter().max().map_or(1, |max_symbol| *max_symbol as u64 + 1);
+    let kmer_size = kmer_size(text);
+    let symbol_at = |position: usize| text.get(position).map_or(0, |symbol| *symbol as u64);
+    let first_multiplier = base.wrapping_pow(kmer_size as u32 - 1);
+
+    let rank_file = scratch_dir.create_file()?;
+    let mut writer = rank_file.writer();
+    let mut kmer = (0..kmer_size).fold(0, |kmer, offset| kmer * base + symbol_at(offset));
+    for position in 0..text.len() {
+        writer.write_all(&(kmer + 1).to_ne_bytes())?;
+        kmer = (kmer - symbol_at(position) * first_multiplier) * base + symbol_at(position + kmer_size);
+    }
+    writer.flush()?;
+    drop(writer);
+    Ok(rank_file)
+}
+
+struct RankReader<R: Read>(R);
+
+impl<R: Read> RankReader<R> {
+    fn read(&mut self) -> io::Result<u64> {
+        let mut bytes = [0; size_of::<u64>()];
+        self.0.read_exact(&mut bytes)?;
+        Ok(u64::from_ne_bytes(bytes))
+    }
+}
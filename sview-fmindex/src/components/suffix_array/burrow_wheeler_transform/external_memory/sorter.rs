@@ -0,0 +1,233 @@
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+// Buffer of each file read or written sequentially
+const IO_BUFFER_SIZE: usize = crate::build_config::SuffixSortConfig::IO_BUFFER_SIZE;
+
+/// Record sorted by the key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Record {
+    pub key: (u64, u64),
+    pub value: u64,
+}
+
+impl Record {
+    const SIZE: usize = 3 * size_of::<u64>();
+
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        writer.write_all(&self.key.0.to_ne_bytes())?;
+        writer.write_all(&self.key.1.to_ne_bytes())?;
+        writer.write_all(&self.value.to_ne_bytes())
+    }
+    fn read_from(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut bytes = [0; Self::SIZE];
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => {},
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        let word = |index: usize| u64::from_ne_bytes(bytes[index * 8..(index + 1) * 8].try_into().unwrap());
+        Ok(Some(Self { key: (word(0), word(1)), value: word(2) }))
+    }
+}
+
+// ================================================
+// Scratch files
+// ================================================
+/// Directory of the scratch files, removed on drop
+pub(super) struct ScratchDir {
+    path: PathBuf,
+    file_count: Cell<usize>,
+}
+
+impl ScratchDir {
+    pub fn create_in(temp_dir: &Path) -> io::Result<Self> {
+        let mut attempt = 0_u32;
+        loop {
+            let path = temp_dir.join(format!("sview-fmindex-scratch-{}-{}", std::process::id(), attempt));
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(Self { path, file_count: Cell::new(0) }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists && attempt < 1024 => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+    pub fn create_file(&self) -> io::Result<ScratchFile> {
+        let file_index = self.file_count.get();
+        let path = self.path.join(format!("{}.tmp", file_index));
+        self.file_count.set(file_index + 1);
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(ScratchFile { path, file })
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Scratch file, removed on drop to free the disk as early as possible
+pub(super) struct ScratchFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl ScratchFile {
+    pub fn writer(&self) -> BufWriter<&File> {
+        BufWriter::with_capacity(IO_BUFFER_SIZE, &self.file)
+    }
+    /// Reader from the offset, independent of the other readers
+    pub fn reader_at(&self, offset: u64) -> io::Result<BufReader<File>> {
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(BufReader::with_capacity(IO_BUFFER_SIZE, file))
+    }
+}
+
+impl Drop for ScratchFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// ================================================
+// Sort
+// ================================================
+/// Sorter spilling the sorted runs of records to the scratch files
+///
+/// The records in memory, and the read buffers of the merge, are kept within the memory budget.
+/// The buffer of the records is allocated once, and reused for every run.
+pub(super) struct ExternalSorter<'d> {
+    scratch_dir: &'d ScratchDir,
+    buffer: Vec<Record>,
+    capacity: usize,
+    max_fan_in: usize,
+    runs: Vec<ScratchFile>,
+}
+
+impl<'d> ExternalSorter<'d> {
+    /// The buffer is not larger than the `record_count` to push.
+    pub fn new(scratch_dir: &'d ScratchDir, memory_budget: usize, record_count: usize) -> Self {
+        let capacity = (memory_budget / Record::SIZE).min(record_count).max(1);
+        let max_fan_in = (memory_budget / IO_BUFFER_SIZE).max(2);
+        Self {
+            scratch_dir,
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+            max_fan_in,
+            runs: Vec::new(),
+        }
+    }
+    pub fn push(&mut self, record: Record) -> io::Result<()> {
+        self.buffer.push(record);
+        if self.buffer.len() == self.capacity {
+            self.spill()?;
+        }
+        Ok(())
+    }
+    pub fn finish(mut self) -> io::Result<SortedRecords> {
+        if self.runs.is_empty() {
+            sort_records(&mut self.buffer);
+            return Ok(SortedRecords::InMemory(std::mem::take(&mut self.buffer).into_iter()));
+        }
+        if !self.buffer.is_empty() {
+            self.spill()?;
+        }
+        // Freed for the read buffers of the merge
+        self.buffer = Vec::new();
+        // Merge the runs until they can be read at once
+        while self.runs.len() > self.max_fan_in {
+            let runs: Vec<ScratchFile> = self.runs.drain(..self.max_fan_in).collect();
+            let merged_run = self.scratch_dir.create_file()?;
+            let mut writer = merged_run.writer();
+            for record in Merger::new(runs)? {
+                record?.write_to(&mut writer)?;
+            }
+            writer.flush()?;
+            drop(writer);
+            self.runs.push(merged_run);
+        }
+        Ok(SortedRecords::Merged(Merger::new(std::mem::take(&mut self.runs))?))
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        sort_records(&mut self.buffer);
+        let run = self.scratch_dir.create_file()?;
+        let mut writer = run.writer();
+        for record in &self.buffer {
+            record.write_to(&mut writer)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        self.buffer.clear();
+        self.runs.push(run);
+        Ok(())
+    }
+}
+
+fn sort_records(records: &mut [Record]) {
+    #[cfg(not(feature = "parallel"))]
+    records.sort_unstable_by_key(|record| record.key);
+    #[cfg(feature = "parallel")]
+    rayon::slice::ParallelSliceMut::par_sort_unstable_by_key(records, |record| record.key);
+}
+
+/// Records in the order of the keys
+pub(super) enum SortedRecords {
+    InMemory(std::vec::IntoIter<Record>),
+    Merged(Merger),
+}
+
+impl Iterator for SortedRecords {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::InMemory(records) => records.next().map(Ok),
+            Self::Merged(merger) => merger.next(),
+        }
+    }
+}
+
+// Head record of each run: key, run index and value
+type RunHead = Reverse<((u64, u64), usize, u64)>;
+
+/// K-way merge of the sorted runs
+pub(super) struct Merger {
+    // Runs are dropped (and removed) with the merger.
+    _runs: Vec<ScratchFile>,
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<RunHead>,
+}
+
+impl Merger {
+    fn new(runs: Vec<ScratchFile>) -> io::Result<Self> {
+        let mut readers = runs.iter().map(|run| run.reader_at(0)).collect::<io::Result<Vec<_>>>()?;
+        let mut heap = BinaryHeap::with_capacity(readers.len());
+        for (run_index, reader) in readers.iter_mut().enumerate() {
+            if let Some(record) = Record::read_from(reader)? {
+                heap.push(Reverse((record.key, run_index, record.value)));
+            }
+        }
+        Ok(Self { _runs: runs, readers, heap })
+    }
+}
+
+impl Iterator for Merger {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((key, run_index, value)) = self.heap.pop()?;
+        match Record::read_from(&mut self.readers[run_index]) {
+            Ok(Some(next_record)) => self.heap.push(Reverse((next_record.key, run_index, next_record.value))),
+            Ok(None) => {},
+            Err(err) => return Some(Err(err)),
+        }
+        Some(Ok(Record { key, value }))
+    }
+}
@@ -1,16 +1,162 @@
 // Type 1: use crate 'bio'
-// This is default version
+// Kept as the reference of the results
 #[allow(unused)]
 mod crate_bio_manual;
 // Type 2: use 'libdivsufsort'
 // Faster, but restrict the environment
 #[cfg(feature = "fastbwt")]
 mod libdivsufsort;
+// Type 3: native SA-IS
+// This is default version
+#[cfg_attr(feature = "fastbwt", allow(unused))]
+mod sais;
+
+// Disk-backed version
+// For the texts of which the suffix array does not fit in memory
+mod external_memory;
+pub use external_memory::get_compressed_suffix_array_and_pidx_while_bwt_in_external_memory;
 
 #[cfg(not(feature = "fastbwt"))]
-pub use crate_bio_manual::get_compressed_suffix_array_and_pidx_while_bwt_with_crate_bio as get_compressed_suffix_array_and_pidx_while_bwt;
+pub use sais::get_compressed_suffix_array_and_pidx_while_bwt_with_sais as get_compressed_suffix_array_and_pidx_while_bwt;
+
+/// 'libdivsufsort' is called at once, so that each phase is reported only at its start and end.
 #[cfg(feature = "fastbwt")]
-pub use libdivsufsort::get_compressed_suffix_array_and_pidx_while_bwt_with_libdivsufsort as get_compressed_suffix_array_and_pidx_while_bwt;
+pub fn get_compressed_suffix_array_and_pidx_while_bwt<P: crate::Position>(
+    text: &mut Vec<u8>,
+    sampling_ratio: P,
+    monitor: &crate::builder::BuildMonitor,
+) -> Result<(Vec<P>, P), crate::BuildError> {
+    use crate::builder::BuildPhase;
+
+    let n = text.len() as u64 + 1;
+    let progress = monitor.start(BuildPhase::SuffixSorting, n)?;
+    let result = libdivsufsort::get_compressed_suffix_array_and_pidx_while_bwt_with_libdivsufsort(text, sampling_ratio);
+    progress.finish()?;
+    monitor.start(BuildPhase::Bwt, n)?.finish()?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod sais_tests {
+    use crate::Position;
+    use crate::builder::BuildMonitor;
+    use crate::tests::random_data::*;
+
+    use super::crate_bio_manual::get_compressed_suffix_array_and_pidx_while_bwt_with_crate_bio as bwt1;
+    use super::sais::get_compressed_suffix_array_and_pidx_while_bwt_with_sais as bwt3;
+
+    #[test]
+    fn sais_result_is_same_with_crate_bio() {
+        let n_test = 100;
+        let min_text_len = 1;
+        let max_text_len = 500;
+        let chr_counts = 1..5;
+
+        for chr_count in chr_counts {
+            for _ in 0..n_test {
+                let chr_list = gen_rand_chr_list(chr_count);
+                let text = gen_rand_text(&chr_list, min_text_len, max_text_len);
+                assert_sais_same_with_crate_bio::<u32>(&text);
+                assert_sais_same_with_crate_bio::<u64>(&text);
+            }
+        }
+        // Repetitive texts, which recurse deeply
+        for text in [vec![1; 300], [1, 2].repeat(200), [2, 1, 1].repeat(150), [3, 1, 2, 1, 2].repeat(99)] {
+            assert_sais_same_with_crate_bio::<u32>(&text);
+        }
+    }
+    fn assert_sais_same_with_crate_bio<P: Position>(text: &[u8]) {
+        for sampling_ratio in 1..4 {
+            let mut bwt_res_1 = text.to_vec();
+            let (suffix_array_1, pidx_1) = bwt1::<P>(&mut bwt_res_1, P::from_u32(sampling_ratio));
+
+            let mut bwt_res_3 = text.to_vec();
+            let (suffix_array_3, pidx_3) = bwt3::<P>(&mut bwt_res_3, P::from_u32(sampling_ratio), &BuildMonitor::default()).unwrap();
+
+            assert_eq!(suffix_array_1, suffix_array_3);
+            assert_eq!(bwt_res_1, bwt_res_3);
+            assert_eq!(pidx_1, pidx_3);
+        }
+    }
+}
+
+#[cfg(test)]
+mod external_memory_tests {
+    use crate::Position;
+    use crate::builder::BuildMonitor;
+    use crate::tests::random_data::*;
+
+    use super::crate_bio_manual::get_compressed_suffix_array_and_pidx_while_bwt_with_crate_bio as bwt1;
+    use super::external_memory::get_compressed_suffix_array_and_pidx_while_bwt_in_external_memory as bwt4;
+    use super::external_memory::sort_in_external_memory as sort4;
+
+    #[test]
+    fn external_memory_result_is_same_with_crate_bio() {
+        let temp_dir = std::env::temp_dir().join(format!("sview-fmindex-external-memory-{}", std::process::id()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        for chr_count in 1..5 {
+            for _ in 0..20 {
+                let chr_list = gen_rand_chr_list(chr_count);
+                let text = gen_rand_text(&chr_list, 1, 300);
+                // Budget of a few records, to spill and merge the runs many times
+                assert_external_memory_same_with_crate_bio::<u32>(&text, &temp_dir, 2000);
+                assert_external_memory_same_with_crate_bio::<u64>(&text, &temp_dir, 1 << 20);
+            }
+        }
+        // Repetitive texts, which take many rounds
+        for text in [vec![1; 300], [1, 2].repeat(200), [2, 1, 1].repeat(150)] {
+            assert_external_memory_same_with_crate_bio::<u32>(&text, &temp_dir, 1000);
+        }
+        // Symbols of large value, with the short k-mers
+        let text: Vec<u8> = (0..500).map(|i| (255 - (i * 7 % 13)) as u8).collect();
+        assert_external_memory_same_with_crate_bio::<u32>(&text, &temp_dir, 1000);
+
+        // Scratch files are removed
+        assert_eq!(std::fs::read_dir(&temp_dir).unwrap().count(), 0);
+        std::fs::remove_dir(&temp_dir).unwrap();
+    }
+    #[test]
+    fn external_memory_discards_the_suffixes_of_unique_ranks() {
+        let temp_dir = std::env::temp_dir().join(format!("sview-fmindex-external-memory-rounds-{}", std::process::id()));
+        std::fs::create_dir_all(&temp_dir).unwrap();
+
+        // Random text with a long repeat, of which the suffixes take many rounds
+        let chr_list = gen_rand_chr_list(4);
+        let random_text = gen_rand_text(&chr_list, 30_000, 30_000);
+        let mut text = random_text.clone();
+        text.extend_from_slice(&random_text[..2_000]);
+        let n = text.len() as u64 + 1;
+
+        let mut bwt_res_1 = text.clone();
+        let (suffix_array_1, pidx_1) = bwt1::<u32>(&mut bwt_res_1, 2);
+        let mut bwt_res_4 = text.clone();
+        let ((suffix_array_4, pidx_4), stats) = sort4::<u32>(&mut bwt_res_4, 2, &temp_dir, 1 << 16, &BuildMonitor::default()).unwrap();
+        assert_eq!(suffix_array_1, suffix_array_4);
+        assert_eq!(bwt_res_1, bwt_res_4);
+        assert_eq!(pidx_1, pidx_4);
+
+        // The prefixes are doubled until the repeat of 2000 symbols is resolved.
+        assert!(stats.rounds >= 8, "rounds: {}", stats.rounds);
+        // Only the suffixes in the two copies of the repeat are sorted again after the first round.
+        assert!(stats.sorted_pairs < stats.rounds as u64 * n / 3, "sorted pairs: {} of {} suffixes in {} rounds", stats.sorted_pairs, n, stats.rounds);
+
+        std::fs::remove_dir(&temp_dir).unwrap();
+    }
+    fn assert_external_memory_same_with_crate_bio<P: Position>(text: &[u8], temp_dir: &std::path::Path, memory_budget: usize) {
+        for sampling_ratio in 1..4 {
+            let mut bwt_res_1 = text.to_vec();
+            let (suffix_array_1, pidx_1) = bwt1::<P>(&mut bwt_res_1, P::from_u32(sampling_ratio));
+
+            let mut bwt_res_4 = text.to_vec();
+            let (suffix_array_4, pidx_4) = bwt4::<P>(&mut bwt_res_4, P::from_u32(sampling_ratio), temp_dir, memory_budget, &BuildMonitor::default()).unwrap();
+
+            assert_eq!(suffix_array_1, suffix_array_4);
+            assert_eq!(bwt_res_1, bwt_res_4);
+            assert_eq!(pidx_1, pidx_4);
+        }
+    }
+}
 
 #[cfg(test)]
 #[cfg(feature = "fastbwt")]
@@ -0,0 +1,308 @@
+use crate::{Position, BuildError};
+use crate::builder::{BuildMonitor, BuildPhase, PhaseProgress, REPORT_INTERVAL};
+
+const SENTINEL_SYMBOL: u8 = 0;
+
+/// Suffix array by SA-IS (Nong, Zhang & Chan, 2009)
+///
+/// The suffix array is built directly in `P`, and the reduced problem of each level
+/// is sorted in the free space of the suffix array.
+/// The BWT and the sampled suffix array are taken in the same pass over the suffix array,
+/// and the suffix array is compressed in place.
+/// With the `parallel` feature, the pass is split over the threads, and the sampled suffix array is collected to a new vector.
+///
+/// The sort is reported at the stages of the top level, and the cancellation is checked in the induced sorts.
+#[inline]
+pub fn get_compressed_suffix_array_and_pidx_while_bwt_with_sais<P: Position>(
+    text: &mut Vec<u8>,
+    sampling_ratio: P,
+    monitor: &BuildMonitor,
+) -> Result<(Vec<P>, P), BuildError> {
+    // The sentinel is appended to the text itself, in the capacity reserved by the builder.
+    text.push(SENTINEL_SYMBOL);
+    let mut suffix_array = vec![P::ZERO; text.len()];
+    let progress = monitor.start(BuildPhase::SuffixSorting, text.len() as u64)?;
+    sais::<u8, P>(text, &mut suffix_array, u8::MAX as usize + 1, &progress, true)?;
+    progress.finish()?;
+
+    // The first row is the suffix of the sentinel, which is not sampled.
+    let sampling_ratio = sampling_ratio.as_usize();
+    let progress = monitor.start(BuildPhase::Bwt, text.len() as u64)?;
+    #[cfg(not(feature = "parallel"))]
+    let (suffix_array, bwt, pidx) = {
+        let mut suffix_array = suffix_array;
+        let mut bwt = Vec::with_capacity(text.len() - 1);
+        let mut pidx = 0;
+        let mut sampled_count = 0;
+        for row in 0..suffix_array.len() {
+            let position = suffix_array[row].as_usize();
+            if position == 0 {
+                pidx = row;
+            } else {
+                bwt.push(text[position - 1]);
+            }
+            if row != 0 && (row - 1) % sampling_ratio == 0 {
+                suffix_array[sampled_count] = suffix_array[row];
+                sampled_count += 1;
+            }
+            if (row + 1) % REPORT_INTERVAL == 0 {
+                progress.set((row + 1) as u64)?;
+            }
+        }
+        suffix_array.truncate(sampled_count);
+        (suffix_array, bwt, pidx)
+    };
+    #[cfg(feature = "parallel")]
+    let (suffix_array, bwt, pidx) = {
+        use rayon::prelude::*;
+
+        let pidx = suffix_array.par_iter().position_any(|position| *position == P::ZERO).unwrap_or(0);
+        let bwt = suffix_array[..pidx].par_iter().chain(suffix_array[pidx + 1..].par_iter())
+            .map(|position| text[position.as_usize() - 1])
+            .collect();
+        let sampled_suffix_array = suffix_array[1..].par_iter().step_by(sampling_ratio).copied().collect();
+        (sampled_suffix_array, bwt, pidx)
+    };
+    progress.finish()?;
+
+    // Change original text to bwt
+    *text = bwt;
+    Ok((suffix_array, P::from_usize(pidx)))
+}
+
+// Symbol of the text at each level
+trait Symbol: Copy {
+    fn rank(self) -> usize;
+}
+impl Symbol for u8 {
+    #[inline(always)]
+    fn rank(self) -> usize {
+        self as usize
+    }
+}
+impl<P: Position> Symbol for P {
+    #[inline(always)]
+    fn rank(self) -> usize {
+        self.as_usize()
+    }
+}
+
+// Sort the suffixes of the text, of which the last symbol is the unique smallest one
+//  - The stages are reported only at the top level, as the reduced problems are sorted inside of it.
+fn sais<C: Symbol, P: Position>(
+    text: &[C],
+    suffix_array: &mut [P],
+    alphabet_size: usize,
+    progress: &PhaseProgress,
+    is_top_level: bool,
+) -> Result<(), BuildError> {
+    let n = text.len();
+    if n == 1 {
+        suffix_array[0] = P::ZERO;
+        return Ok(());
+    }
+    // The builder rejects the texts of which the positions reach the largest position, which marks the empty slots.
+    let empty = P::from_u64(u64::MAX);
+    let types = SuffixTypes::new(text);
+    let mut buckets = vec![P::ZERO; alphabet_size];
+
+    // 1) Sort the LMS substrings
+    suffix_array.fill(empty);
+    set_bucket_tails(text, &mut buckets);
+    for position in (1..n).filter(|&position| types.is_lms(position)) {
+        let bucket = &mut buckets[text[position].rank()];
+        *bucket = P::from_usize(bucket.as_usize() - 1);
+        suffix_array[bucket.as_usize()] = P::from_usize(position);
+    }
+    induce(text, suffix_array, &types, &mut buckets, progress)?;
+    if is_top_level {
+        progress.set(n as u64 / 3)?;
+    }
+
+    // 2) Move the sorted LMS substrings to the front
+    let mut lms_count = 0;
+    for row in 0..n {
+        let position = suffix_array[row];
+        if types.is_lms(position.as_usize()) {
+            suffix_array[lms_count] = position;
+            lms_count += 1;
+        }
+    }
+
+    // 3) Name the LMS substrings
+    //  - The LMS positions are at least 2 apart, so that the name of the position `p` is stored at `lms_count + p / 2`.
+    suffix_array[lms_count..].fill(empty);
+    let mut name_count = 0;
+    let mut previous_position = None;
+    for row in 0..lms_count {
+        let position = suffix_array[row].as_usize();
+        let is_same_as_previous = previous_position.is_some_and(|previous_position| {
+            lms_substring_eq(text, &types, previous_position, position)
+        });
+        if !is_same_as_previous {
+            name_count += 1;
+            previous_position = Some(position);
+        }
+        suffix_array[lms_count + position / 2] = P::from_usize(name_count - 1);
+    }
+    //  - Reduced string at the end of the suffix array, in the order of the positions
+    let mut tail = n;
+    for index in (lms_count..n).rev() {
+        if suffix_array[index] != empty {
+            tail -= 1;
+            suffix_array[tail] = suffix_array[index];
+        }
+    }
+
+    // 4) Sort the suffixes of the reduced string
+    let (reduced_suffix_array, rest) = suffix_array.split_at_mut(lms_count);
+    let reduced_text = &mut rest[n - 2 * lms_count..];
+    if name_count < lms_count {
+        sais::<P, P>(reduced_text, reduced_suffix_array, name_count, progress, false)?;
+    } else {
+        for (index, name) in reduced_text.iter().enumerate() {
+            reduced_suffix_array[name.as_usize()] = P::from_usize(index);
+        }
+    }
+    //  - Map to the LMS positions
+    for (slot, position) in reduced_text.iter_mut().zip((1..n).filter(|&position| types.is_lms(position))) {
+        *slot = P::from_usize(position);
+    }
+    for row in 0..lms_count {
+        reduced_suffix_array[row] = reduced_text[reduced_suffix_array[row].as_usize()];
+    }
+    if is_top_level {
+        progress.set(n as u64 * 2 / 3)?;
+    }
+
+    // 5) Induce the suffix array from the sorted LMS suffixes
+    suffix_array[lms_count..].fill(empty);
+    set_bucket_tails(text, &mut buckets);
+    for row in (0..lms_count).rev() {
+        let position = suffix_array[row];
+        suffix_array[row] = empty;
+        let bucket = &mut buckets[text[position.as_usize()].rank()];
+        *bucket = P::from_usize(bucket.as_usize() - 1);
+        suffix_array[bucket.as_usize()] = position;
+    }
+    induce(text, suffix_array, &types, &mut buckets, progress)
+}
+
+// Induce the L-type suffixes from the heads of the buckets, and the S-type suffixes from the tails
+fn induce<C: Symbol, P: Position>(
+    text: &[C],
+    suffix_array: &mut [P],
+    types: &SuffixTypes,
+    buckets: &mut [P],
+    progress: &PhaseProgress,
+) -> Result<(), BuildError> {
+    let empty = P::from_u64(u64::MAX);
+    set_bucket_heads(text, buckets);
+    for row in 0..text.len() {
+        if row % REPORT_INTERVAL == 0 {
+            progress.check_cancelled()?;
+        }
+        let position = suffix_array[row];
+        if position != empty && position != P::ZERO && !types.is_s(position.as_usize() - 1) {
+            let previous_position = position.as_usize() - 1;
+            let bucket = &mut buckets[text[previous_position].rank()];
+            suffix_array[bucket.as_usize()] = P::from_usize(previous_position);
+            *bucket = P::from_usize(bucket.as_usize() + 1);
+        }
+    }
+    set_bucket_tails(text, buckets);
+    for row in (0..text.len()).rev() {
+        if row % REPORT_INTERVAL == 0 {
+            progress.check_cancelled()?;
+        }
+        let position = suffix_array[row];
+        if position != empty && position != P::ZERO && types.is_s(position.as_usize() - 1) {
+            let previous_position = position.as_usize() - 1;
+            let bucket = &mut buckets[text[previous_position].rank()];
+            *bucket = P::from_usize(bucket.as_usize() - 1);
+            suffix_array[bucket.as_usize()] = P::from_usize(previous_position);
+        }
+    }
+    Ok(())
+}
+
+// The counts are taken again for each use, to keep a single array of the alphabet size.
+fn count_symbols<C: Symbol, P: Position>(text: &[C], buckets: &mut [P]) {
+    buckets.fill(P::ZERO);
+    text.iter().for_each(|symbol| buckets[symbol.rank()] += P::ONE);
+}
+fn set_bucket_heads<C: Symbol, P: Position>(text: &[C], buckets: &mut [P]) {
+    count_symbols(text, buckets);
+    let mut sum = P::ZERO;
+    buckets.iter_mut().for_each(|bucket| {
+        let count = *bucket;
+        *bucket = sum;
+        sum += count;
+    });
+}
+fn set_bucket_tails<C: Symbol, P: Position>(text: &[C], buckets: &mut [P]) {
+    count_symbols(text, buckets);
+    let mut sum = P::ZERO;
+    buckets.iter_mut().for_each(|bucket| {
+        sum += *bucket;
+        *bucket = sum;
+    });
+}
+
+// Whether the LMS substrings starting at the positions are equal
+fn lms_substring_eq<C: Symbol>(text: &[C], types: &SuffixTypes, first: usize, second: usize) -> bool {
+    // The substring of the sentinel is unique.
+    let last = text.len() - 1;
+    if first == last || second == last {
+        return first == second;
+    }
+    let mut offset = 0;
+    loop {
+        let (first_position, second_position) = (first + offset, second + offset);
+        if text[first_position].rank() != text[second_position].rank()
+            || types.is_s(first_position) != types.is_s(second_position)
+        {
+            return false;
+        }
+        if offset > 0 && (types.is_lms(first_position) || types.is_lms(second_position)) {
+            return types.is_lms(first_position) && types.is_lms(second_position);
+        }
+        offset += 1;
+    }
+}
+
+// Bit of S-type for each suffix
+struct SuffixTypes {
+    bits: Vec<u64>,
+}
+
+impl SuffixTypes {
+    fn new<C: Symbol>(text: &[C]) -> Self {
+        let n = text.len();
+        let mut types = Self { bits: vec![0; n.div_ceil(64)] };
+        // The sentinel is S-type.
+        types.set_s(n - 1);
+        let mut is_s = true;
+        for position in (0..n - 1).rev() {
+            let (symbol, next_symbol) = (text[position].rank(), text[position + 1].rank());
+            is_s = symbol < next_symbol || (symbol == next_symbol && is_s);
+            if is_s {
+                types.set_s(position);
+            }
+        }
+        types
+    }
+    #[inline(always)]
+    fn set_s(&mut self, position: usize) {
+        self.bits[position / 64] |= 1 << (position % 64);
+    }
+    #[inline(always)]
+    fn is_s(&self, position: usize) -> bool {
+        self.bits[position / 64] & (1 << (position % 64)) != 0
+    }
+    // Leftmost S-type
+    #[inline(always)]
+    fn is_lms(&self, position: usize) -> bool {
+        position > 0 && self.is_s(position) && !self.is_s(position - 1)
+    }
+}
@@ -1,9 +1,19 @@
-use crate::Position;
-use super::{Aligned, Header, View};
+use core::convert::Infallible;
 
+use crate::{Position, LoadError, VerifyError};
+use super::{Aligned, Header, View, ChecksumHeader, SectionSize, SectionKind, items_size};
+
+#[cfg(feature = "std")]
 mod burrow_wheeler_transform;
-use burrow_wheeler_transform::get_compressed_suffix_array_and_pidx_while_bwt;
+#[cfg(feature = "std")]
+use burrow_wheeler_transform::{
+    get_compressed_suffix_array_and_pidx_while_bwt,
+    get_compressed_suffix_array_and_pidx_while_bwt_in_external_memory,
+};
+#[cfg(feature = "std")]
 use num_integer::div_rem;
+#[cfg(feature = "std")]
+use crate::{BuildError, builder::{BuildMonitor, BuildPhase, REPORT_INTERVAL}};
 use zerocopy::IntoBytes;
 
 #[repr(C)]
@@ -27,18 +37,44 @@ pub struct SuffixArrayView<'a, P: Position> {
 
 impl SuffixArrayHeader {
     pub fn suffix_array_raw_size<P: Position>(&self) -> usize {
-        self.suffix_array_len as usize * std::mem::size_of::<P>()
+        items_size(self.suffix_array_len, core::mem::size_of::<P>())
     }
     pub fn suffix_array_aligned_size<P: Position, A: Aligned>(&self) -> usize {
         A::aligned_size(self.suffix_array_raw_size::<P>())
     }
+    pub fn body_sections<P: Position, A: Aligned>(&self) -> [SectionSize; 1] {
+        [
+            SectionSize {
+                kind: SectionKind::SuffixArray,
+                raw_size: self.suffix_array_raw_size::<P>(),
+                aligned_size: self.suffix_array_aligned_size::<P, A>(),
+                item_size: core::mem::size_of::<P>(),
+            },
+        ]
+    }
 }
 
-impl Header for SuffixArrayHeader {}
+impl Header for SuffixArrayHeader {
+    fn swap_byte_order(&mut self) {
+        self.sampling_ratio = self.sampling_ratio.swap_bytes();
+        self.suffix_array_len = self.suffix_array_len.swap_bytes();
+    }
+}
+
+// ================================================
+// Validate
+// ================================================
+impl SuffixArrayHeader {
+    /// Check that the sampling ratio is valid
+    pub fn is_valid(&self) -> bool {
+        self.sampling_ratio >= 1
+    }
+}
 
 // ================================================
 // Build
 // ================================================
+#[cfg(feature = "std")]
 impl SuffixArrayHeader {
     pub fn new(
         text_len: u64,
@@ -58,15 +94,52 @@ impl SuffixArrayHeader {
         &self,
         text: &mut Vec<u8>,
         blob: &mut [u8],
-    ) -> P {
+        monitor: &BuildMonitor,
+    ) -> Result<P, BuildError> {
         let (compressed_suffix_array, pidx) = get_compressed_suffix_array_and_pidx_while_bwt::<P>(
             text,
             P::from_u32(self.sampling_ratio),
-        );
+            monitor,
+        )?;
 
-        blob[..self.suffix_array_raw_size::<P>()].copy_from_slice(compressed_suffix_array.as_bytes());
+        self.write_sampled_suffix_array(&compressed_suffix_array, blob, monitor)?;
 
-        pidx
+        Ok(pidx)
+    }
+    /// Same as `write_to_blob_and_get_sentinel_index`, sorting the suffixes in the scratch files of `temp_dir`
+    pub fn write_to_blob_and_get_sentinel_index_in_external_memory<P: Position>(
+        &self,
+        text: &mut Vec<u8>,
+        blob: &mut [u8],
+        temp_dir: &std::path::Path,
+        memory_budget: usize,
+        monitor: &BuildMonitor,
+    ) -> Result<P, BuildError> {
+        let (compressed_suffix_array, pidx) = get_compressed_suffix_array_and_pidx_while_bwt_in_external_memory::<P>(
+            text,
+            P::from_u32(self.sampling_ratio),
+            temp_dir,
+            memory_budget,
+            monitor,
+        )?;
+
+        self.write_sampled_suffix_array(&compressed_suffix_array, blob, monitor)?;
+
+        Ok(pidx)
+    }
+    fn write_sampled_suffix_array<P: Position>(
+        &self,
+        compressed_suffix_array: &[P],
+        blob: &mut [u8],
+        monitor: &BuildMonitor,
+    ) -> Result<(), BuildError> {
+        let progress = monitor.start(BuildPhase::SuffixArraySampling, compressed_suffix_array.len() as u64)?;
+        let blob: &mut [P] = zerocopy::FromBytes::mut_from_bytes(&mut blob[..self.suffix_array_raw_size::<P>()]).unwrap();
+        for (blob_chunk, chunk) in blob.chunks_mut(REPORT_INTERVAL).zip(compressed_suffix_array.chunks(REPORT_INTERVAL)) {
+            blob_chunk.copy_from_slice(chunk);
+            progress.add(chunk.len() as u64)?;
+        }
+        progress.finish()
     }
 }
 
@@ -79,28 +152,80 @@ impl<'a, P:Position> View<'a> for SuffixArrayView<'a, P> {
     fn aligned_body_size<A: Aligned>(header: &Self::Header) -> usize {
         header.suffix_array_aligned_size::<P, A>()
     }
-    fn load_from_body<A: Aligned>(header: &Self::Header, body_blob: &'a [u8]) -> Self {
+    fn load_from_body<A: Aligned>(header: &Self::Header, body_blob: &'a [u8]) -> Result<Self, LoadError> {
         let sampling_ratio = P::from_u32(header.sampling_ratio);
         
-        let suffix_array: &[P] = zerocopy::FromBytes::ref_from_bytes(
-            &body_blob[..header.suffix_array_raw_size::<P>()]
-        ).unwrap();
+        let suffix_array: &[P] = body_blob.get(..header.suffix_array_raw_size::<P>())
+            .and_then(|bytes| zerocopy::FromBytes::ref_from_bytes(bytes).ok())
+            .ok_or(LoadError::InvalidSection("suffix array"))?;
             
-        Self { sampling_ratio, suffix_array }
+        Ok(Self { sampling_ratio, suffix_array })
     }
 }
 
 // ================================================
-// Locate
+// Checksum
 // ================================================
-impl<'a, P: Position> SuffixArrayView<'a, P> {
-    pub fn sampling_ratio(&self) -> P {
-        self.sampling_ratio
+impl<P: Position> SuffixArrayView<'_, P> {
+    pub fn write_checksums(&self, checksum_header: &mut ChecksumHeader) {
+        checksum_header.suffix_array = ChecksumHeader::checksum_of(self.suffix_array.as_bytes());
+    }
+}
+
+// ================================================
+// Validate
+// ================================================
+impl<P: Position> SuffixArrayView<'_, P> {
+    pub fn validate_structure(&self, text_len: P) -> Result<(), VerifyError> {
+        let (q, r) = text_len.div_rem_with_u32(self.sampling_ratio.as_u32());
+        let suffix_array_len = if r == 0 { q } else { q + P::ONE };
+        if self.suffix_array.len() != suffix_array_len.as_usize() {
+            return Err(VerifyError::InvalidStructure {
+                section: "suffix array",
+                reason: "does not match the text length",
+            });
+        }
+        if self.suffix_array.iter().any(|&location| location >= text_len) {
+            return Err(VerifyError::InvalidStructure {
+                section: "suffix array",
+                reason: "has a location out of the text",
+            });
+        }
+        Ok(())
     }
-    pub fn get_location_of(
+}
+
+// ================================================
+// Locate
+// ================================================
+/// Sampled suffix array read by the queries
+///
+/// `SuffixArrayView` reads it from the slice of blob, which never fails.
+/// The other storages can fail to read it.
+pub trait SuffixArrayItems<P: Position> {
+    type Error;
+
+    fn sampling_ratio(&self) -> P;
+    fn location(&self, index: usize) -> Result<P, Self::Error>;
+
+    #[inline]
+    fn get_location_of(
         &self,
         position: P,
-    ) -> P {
-        self.suffix_array[(position / self.sampling_ratio).as_usize()]
+    ) -> Result<P, Self::Error> {
+        self.location((position / self.sampling_ratio()).as_usize())
+    }
+}
+
+impl<P: Position> SuffixArrayItems<P> for SuffixArrayView<'_, P> {
+    type Error = Infallible;
+
+    #[inline]
+    fn sampling_ratio(&self) -> P {
+        self.sampling_ratio
+    }
+    #[inline]
+    fn location(&self, index: usize) -> Result<P, Infallible> {
+        Ok(self.suffix_array[index])
     }
 }
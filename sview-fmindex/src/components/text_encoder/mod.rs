@@ -1,8 +1,13 @@
 use super::Header;
 
-pub trait TextEncoder: Header {
+pub trait TextEncoder: Header + Send + Sync {
+    /// The kind of encoder recorded in the blob header.
+    const KIND: u8;
+
     /// The index of the given symbol.
     fn idx_of(&self, sym: u8) -> u8;
+    /// Check that every symbol is encoded to an index below the `symbol_count`.
+    fn encodes_within(&self, symbol_count: u32) -> bool;
 }
 
 pub mod text_encoders;
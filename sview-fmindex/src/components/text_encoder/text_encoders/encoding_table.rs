@@ -6,9 +6,14 @@ use super::{Header, TextEncoder};
 #[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
 pub struct EncodingTable([u8; 256]);
 impl TextEncoder for EncodingTable {
+    const KIND: u8 = 2;
+
     fn idx_of(&self, sym: u8) -> u8 {
         unsafe { *self.0.get_unchecked(sym as usize) }
     }
+    fn encodes_within(&self, symbol_count: u32) -> bool {
+        self.0.iter().all(|&idx| (idx as u32) < symbol_count)
+    }
 }
 
 impl EncodingTable {
@@ -35,6 +40,10 @@ impl EncodingTable {
     pub fn symbol_count(&self) -> u32 {
         *self.0.iter().max().unwrap() as u32 + 1
     }
+    /// Index of each byte in the table
+    pub fn mapping(&self) -> &[u8; 256] {
+        &self.0
+    }
 }
 
 impl Header for EncodingTable {}
\ No newline at end of file
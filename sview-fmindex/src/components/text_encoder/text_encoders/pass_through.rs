@@ -6,10 +6,16 @@ use super::{Header, TextEncoder};
 pub struct PassThrough;
 
 impl TextEncoder for PassThrough {
+    const KIND: u8 = 1;
+
     #[inline(always)]
     fn idx_of(&self, sym: u8) -> u8 {
         sym
     }
+    // The symbols are already indices given by the caller.
+    fn encodes_within(&self, _symbol_count: u32) -> bool {
+        true
+    }
 }
 
 impl Header for PassThrough {}
@@ -0,0 +1,297 @@
+use alloc::vec::Vec;
+use zerocopy::FromBytes;
+
+use crate::Position;
+use super::{
+    Aligned, Header, Block, PrefixAlign, ByteOrder,
+    TextEncoder, CountArrayHeader, SuffixArrayHeader, BwmHeader, ChecksumHeader,
+};
+
+/// Kind of the section listed in the table of contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionKind {
+    // Headers
+    TextEncoder,
+    CountArrayHeader,
+    SuffixArrayHeader,
+    BwmHeader,
+    ChecksumHeader,
+    // Bodies
+    CountArray,
+    KmerMultiplier,
+    KmerCountTable,
+    SuffixArray,
+    SentinelIndex,
+    RankCheckpoints,
+    Blocks,
+    // Optional
+    Metadata,
+}
+
+impl SectionKind {
+    /// Code recorded in the table of contents
+    pub fn code(self) -> u32 {
+        match self {
+            Self::TextEncoder => 1,
+            Self::CountArrayHeader => 2,
+            Self::SuffixArrayHeader => 3,
+            Self::BwmHeader => 4,
+            Self::ChecksumHeader => 5,
+            Self::CountArray => 16,
+            Self::KmerMultiplier => 17,
+            Self::KmerCountTable => 18,
+            Self::SuffixArray => 19,
+            Self::SentinelIndex => 20,
+            Self::RankCheckpoints => 21,
+            Self::Blocks => 22,
+            Self::Metadata => 32,
+        }
+    }
+    /// Returns `None` for the kinds unknown to this version
+    pub fn from_code(code: u32) -> Option<Self> {
+        let kind = match code {
+            1 => Self::TextEncoder,
+            2 => Self::CountArrayHeader,
+            3 => Self::SuffixArrayHeader,
+            4 => Self::BwmHeader,
+            5 => Self::ChecksumHeader,
+            16 => Self::CountArray,
+            17 => Self::KmerMultiplier,
+            18 => Self::KmerCountTable,
+            19 => Self::SuffixArray,
+            20 => Self::SentinelIndex,
+            21 => Self::RankCheckpoints,
+            22 => Self::Blocks,
+            32 => Self::Metadata,
+            _ => return None,
+        };
+        Some(kind)
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::TextEncoder => "text encoder",
+            Self::CountArrayHeader => "count array header",
+            Self::SuffixArrayHeader => "suffix array header",
+            Self::BwmHeader => "bwm header",
+            Self::ChecksumHeader => "checksum header",
+            Self::CountArray => "count array",
+            Self::KmerMultiplier => "k-mer multiplier",
+            Self::KmerCountTable => "k-mer count table",
+            Self::SuffixArray => "suffix array",
+            Self::SentinelIndex => "sentinel index",
+            Self::RankCheckpoints => "rank checkpoints",
+            Self::Blocks => "blocks",
+            Self::Metadata => "metadata",
+        }
+    }
+    /// The index can not be read without the required sections
+    pub fn is_required(self) -> bool {
+        !matches!(self, Self::Metadata)
+    }
+}
+
+#[repr(C)]
+#[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TocHeader {
+    section_count: u32,
+    _padding: u32,
+}
+
+/// Entry of the table of contents
+#[repr(C)]
+#[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TocEntry {
+    /// Code of the `SectionKind`
+    pub kind: u32,
+    pub flags: u32,
+    /// Offset in bytes from the start of the blob
+    pub offset: u64,
+    /// Size in bytes, excluding the padding for alignment
+    pub length: u64,
+}
+
+impl TocEntry {
+    /// The section must be understood to read the blob.
+    /// Sections without this flag can be skipped by the readers that do not know the kind.
+    pub const FLAG_REQUIRED: u32 = 1;
+
+    pub fn is_required(&self) -> bool {
+        self.flags & Self::FLAG_REQUIRED != 0
+    }
+    pub fn end(&self) -> u64 {
+        self.offset.saturating_add(self.length)
+    }
+}
+
+/// Table of contents (TOC) of the blob
+///
+/// Follows the magic number and the type descriptor,
+/// and lists the kind, offset and length of every other section.
+/// For the format version 0.0, the TOC is derived from the headers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Toc {
+    entries: Vec<TocEntry>,
+}
+
+impl Toc {
+    /// Lay out the sections in order from the `start_offset`
+    pub fn from_sections(
+        start_offset: usize,
+        sections: impl IntoIterator<Item = (SectionKind, usize, usize)>, // kind, raw size, aligned size
+    ) -> Self {
+        let mut offset = start_offset;
+        let entries = sections.into_iter().map(|(kind, raw_size, aligned_size)| {
+            let entry = TocEntry {
+                kind: kind.code(),
+                flags: if kind.is_required() { TocEntry::FLAG_REQUIRED } else { 0 },
+                offset: offset as u64,
+                length: raw_size as u64,
+            };
+            offset += aligned_size;
+            entry
+        }).collect();
+        Self { entries }
+    }
+    /// Lay out the sections of the current format version
+    ///
+    /// The metadata of `metadata_len` bytes follows the bodies, if present.
+    pub fn of_current_layout<P: Position, B: Block, E: TextEncoder>(
+        text_encoder: &E,
+        count_array_header: &CountArrayHeader,
+        suffix_array_header: &SuffixArrayHeader,
+        bwm_header: &BwmHeader,
+        metadata_len: Option<usize>,
+    ) -> Self {
+        let header_sections = [
+            header_section::<B>(SectionKind::TextEncoder, text_encoder),
+            header_section::<B>(SectionKind::CountArrayHeader, count_array_header),
+            header_section::<B>(SectionKind::SuffixArrayHeader, suffix_array_header),
+            header_section::<B>(SectionKind::BwmHeader, bwm_header),
+            header_section::<B>(SectionKind::ChecksumHeader, &ChecksumHeader::default()),
+        ];
+        let body_sections = count_array_header.body_sections::<P, B>().into_iter()
+            .chain(suffix_array_header.body_sections::<P, B>())
+            .chain(bwm_header.body_sections::<P, B, B>())
+            .map(|section_size| (section_size.kind, section_size.raw_size, section_size.aligned_size));
+        let metadata_section = metadata_len.map(|len| (SectionKind::Metadata, len, B::aligned_size(len)));
+
+        let sections = header_sections.into_iter()
+            .chain(body_sections)
+            .chain(metadata_section)
+            .collect::<Vec<_>>();
+        let start_offset = PrefixAlign::aligned_size(PREFIX_SIZE)
+            + Self::aligned_size_of::<B>(sections.len());
+        Self::from_sections(start_offset, sections)
+    }
+
+    pub fn entries(&self) -> &[TocEntry] {
+        &self.entries
+    }
+    /// The first entry of the kind
+    pub fn entry(&self, kind: SectionKind) -> Option<&TocEntry> {
+        self.entries.iter().find(|entry| entry.kind == kind.code())
+    }
+    /// End of the last section, aligned by `A`
+    pub fn aligned_end<A: Aligned>(&self) -> u64 {
+        self.entries.iter()
+            .map(|entry| A::aligned_size(usize::try_from(entry.end()).unwrap_or(usize::MAX)) as u64)
+            .max()
+            .unwrap_or(0)
+    }
+    /// Size of the TOC with `section_count` entries, aligned by `A`
+    pub fn aligned_size_of<A: Aligned>(section_count: usize) -> usize {
+        A::aligned_size(
+            core::mem::size_of::<TocHeader>() + section_count * core::mem::size_of::<TocEntry>()
+        )
+    }
+    /// Size of the TOC in blob, excluding the padding for alignment
+    pub fn raw_size(&self) -> usize {
+        core::mem::size_of::<TocHeader>() + self.entries.len() * core::mem::size_of::<TocEntry>()
+    }
+
+    // Read & write
+    /// Size of the TOC header, which lists the number of entries
+    #[cfg(feature = "std")]
+    pub const HEADER_SIZE: usize = core::mem::size_of::<TocHeader>();
+    /// Size of the TOC starting at the blob, read from the TOC header in the byte order of the blob
+    ///
+    /// Returns `None` if the blob is shorter than the TOC header.
+    #[cfg(feature = "std")]
+    pub fn raw_size_in_blob(blob: &[u8], byte_order: ByteOrder) -> Option<usize> {
+        let (mut toc_header, _) = TocHeader::read_from_prefix(blob).ok()?;
+        if byte_order != ByteOrder::NATIVE {
+            toc_header.swap_byte_order();
+        }
+        let section_count = usize::try_from(toc_header.section_count).ok()?;
+        section_count.checked_mul(core::mem::size_of::<TocEntry>())?.checked_add(Self::HEADER_SIZE)
+    }
+    /// Read the TOC in the byte order of the blob
+    ///
+    /// Returns `None` if the blob is shorter than the listed entries.
+    pub fn read_from_blob(blob: &[u8], byte_order: ByteOrder) -> Option<Self> {
+        let is_swapped = byte_order != ByteOrder::NATIVE;
+        let (mut toc_header, mut remaining_bytes) = TocHeader::read_from_prefix(blob).ok()?;
+        if is_swapped {
+            toc_header.swap_byte_order();
+        }
+        let section_count = usize::try_from(toc_header.section_count).ok()?;
+        if remaining_bytes.len() / core::mem::size_of::<TocEntry>() < section_count {
+            return None;
+        }
+        let mut entries = Vec::with_capacity(section_count);
+        for _ in 0..section_count {
+            let (mut entry, next_bytes) = TocEntry::read_from_prefix(remaining_bytes).ok()?;
+            if is_swapped {
+                entry.swap_byte_order();
+            }
+            entries.push(entry);
+            remaining_bytes = next_bytes;
+        }
+        Some(Self { entries })
+    }
+    /// Write the TOC in the byte order of the blob
+    pub fn write_to_blob(&self, blob: &mut [u8], byte_order: ByteOrder) {
+        let is_swapped = byte_order != ByteOrder::NATIVE;
+        let mut toc_header = TocHeader {
+            section_count: self.entries.len() as u32,
+            _padding: 0,
+        };
+        if is_swapped {
+            toc_header.swap_byte_order();
+        }
+        toc_header.write_to_blob(blob);
+        let mut offset = core::mem::size_of::<TocHeader>();
+        self.entries.iter().for_each(|entry| {
+            let mut entry = *entry;
+            if is_swapped {
+                entry.swap_byte_order();
+            }
+            entry.write_to_blob(&mut blob[offset..]);
+            offset += core::mem::size_of::<TocEntry>();
+        });
+    }
+}
+
+/// Size of the magic number and the type descriptor
+pub const PREFIX_SIZE: usize = 16;
+
+/// Kind, raw size and aligned size of the header
+pub(crate) fn header_section<A: Aligned>(kind: SectionKind, header: &impl Header) -> (SectionKind, usize, usize) {
+    (kind, header.as_bytes().len(), header.aligned_size::<A>())
+}
+
+impl Header for TocHeader {
+    fn swap_byte_order(&mut self) {
+        self.section_count = self.section_count.swap_bytes();
+    }
+}
+impl Header for TocEntry {
+    fn swap_byte_order(&mut self) {
+        self.kind = self.kind.swap_bytes();
+        self.flags = self.flags.swap_bytes();
+        self.offset = self.offset.swap_bytes();
+        self.length = self.length.swap_bytes();
+    }
+}
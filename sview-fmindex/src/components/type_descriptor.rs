@@ -0,0 +1,129 @@
+use crate::Position;
+use super::{Header, TextEncoder, Block};
+
+/// Descriptor of the types that the FM-index blob was built with
+///
+/// Stored right after the magic number, so that a blob is never loaded
+/// with a different position, block, vector or text encoder type.
+#[repr(C)]
+#[derive(zerocopy::FromBytes, zerocopy::IntoBytes, zerocopy::Immutable, zerocopy::KnownLayout)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeDescriptor {
+    /// Bit width of the position (32 or 64)
+    pub position_bits: u8,
+    /// Count of vectors in the block (2 to 6)
+    pub block_vector_count: u8,
+    /// Bit width of the vector in the block (32, 64 or 128)
+    pub vector_bits: u8,
+    /// Kind of the text encoder (1: `PassThrough`, 2: `EncodingTable`)
+    pub text_encoder_kind: u8,
+    _padding: [u8; 4],
+}
+
+impl TypeDescriptor {
+    pub fn of<P: Position, B: Block, E: TextEncoder>() -> Self {
+        Self {
+            position_bits: P::BITS as u8,
+            block_vector_count: B::VECTOR_COUNT as u8,
+            vector_bits: B::BLOCK_LEN as u8,
+            text_encoder_kind: E::KIND,
+            _padding: [0; 4],
+        }
+    }
+    /// The same types, with the vector of the given bit width in the block
+    #[cfg(feature = "std")]
+    pub(crate) fn with_vector_bits(self, vector_bits: u8) -> Self {
+        Self { vector_bits, ..self }
+    }
+    fn text_encoder_name(&self) -> &'static str {
+        match self.text_encoder_kind {
+            1 => "PassThrough",
+            2 => "EncodingTable",
+            _ => "Unknown",
+        }
+    }
+}
+
+impl Header for TypeDescriptor {}
+
+impl core::fmt::Display for TypeDescriptor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "<u{}, Block{}<u{}>, {}>",
+            self.position_bits,
+            self.block_vector_count,
+            self.vector_bits,
+            self.text_encoder_name(),
+        )
+    }
+}
+
+/// Match the type descriptor to the supported types
+///
+/// In each arm, the given identifiers are aliased to the position, block and text encoder types,
+/// and the body is evaluated. The fallback is evaluated for unsupported descriptors.
+macro_rules! match_type_descriptor {
+    ($type_descriptor:expr, |$p:ident, $b:ident, $e:ident| $body:expr, $fallback:expr) => {{
+        let type_descriptor: &$crate::TypeDescriptor = &$type_descriptor;
+        match type_descriptor.position_bits {
+            32 => {
+                type $p = u32;
+                match_type_descriptor!(@block type_descriptor, $b, $e, $body, $fallback)
+            },
+            64 => {
+                type $p = u64;
+                match_type_descriptor!(@block type_descriptor, $b, $e, $body, $fallback)
+            },
+            _ => $fallback,
+        }
+    }};
+    (@block $type_descriptor:ident, $b:ident, $e:ident, $body:expr, $fallback:expr) => {
+        match_type_descriptor!(
+            @block_of $type_descriptor, $b, $e, $body, $fallback,
+            [(2, Block2), (3, Block3), (4, Block4), (5, Block5), (6, Block6)],
+            [(32, u32), (64, u64), (128, u128)]
+        )
+    };
+    (
+        @block_of $type_descriptor:ident, $b:ident, $e:ident, $body:expr, $fallback:expr,
+        [$(($count:literal, $block:ident)),*],
+        $vectors:tt
+    ) => {
+        match $type_descriptor.block_vector_count {
+            $(
+                $count => match_type_descriptor!(@vector_of $type_descriptor, $b, $e, $body, $fallback, $block, $vectors),
+            )*
+            _ => $fallback,
+        }
+    };
+    (
+        @vector_of $type_descriptor:ident, $b:ident, $e:ident, $body:expr, $fallback:expr,
+        $block:ident,
+        [$(($bits:literal, $vector:ty)),*]
+    ) => {
+        match $type_descriptor.vector_bits {
+            $(
+                $bits => {
+                    type $b = $crate::blocks::$block<$vector>;
+                    match_type_descriptor!(@encoder $type_descriptor, $e, $body, $fallback)
+                },
+            )*
+            _ => $fallback,
+        }
+    };
+    (@encoder $type_descriptor:ident, $e:ident, $body:expr, $fallback:expr) => {
+        match $type_descriptor.text_encoder_kind {
+            1 => {
+                type $e = $crate::text_encoders::PassThrough;
+                $body
+            },
+            2 => {
+                type $e = $crate::text_encoders::EncodingTable;
+                $body
+            },
+            _ => $fallback,
+        }
+    };
+}
+pub(crate) use match_type_descriptor;
@@ -0,0 +1,95 @@
+use zerocopy::IntoBytes;
+
+use crate::{
+    LoadError,
+    // traits
+    Position, Block,
+    components::{Header, TextEncoder, ChecksumHeader, ByteOrder, SectionKind, PREFIX_SIZE},
+    load_from_blob::{BlobHeaders, read_prefix},
+    any_fm_index::dyn_types,
+};
+
+/// Rewrite the blob for the other byte order
+///
+/// The blob can be in either byte order, and is not required to be aligned.
+/// Every integer is reversed and the checksums are computed again,
+/// so the converted blob is loaded on the targets with the other byte order.
+/// The `new_blob` must have the same size as the `blob`.
+/// Only the blobs of the current format version are converted.
+///
+/// Returns the byte order of the converted blob.
+pub fn convert_endianness(blob: &[u8], new_blob: &mut [u8]) -> Result<ByteOrder, LoadError> {
+    if blob.len() != new_blob.len() {
+        return Err(LoadError::MismatchedBlobSize(blob.len(), new_blob.len()));
+    }
+    let (magic_number, type_descriptor, _) = read_prefix(blob)?;
+    if !magic_number.is_current_version() {
+        let (major, minor) = magic_number.version();
+        return Err(LoadError::OutdatedVersion { major, minor });
+    }
+    let byte_order = magic_number.byte_order().ok_or(LoadError::InvalidFormat)?;
+
+    dyn_types(&type_descriptor)?.convert_endianness(blob, new_blob, byte_order)
+}
+
+pub(crate) fn convert_as<P: Position, B: Block, E: TextEncoder>(
+    blob: &[u8],
+    new_blob: &mut [u8],
+    byte_order: ByteOrder,
+) -> Result<ByteOrder, LoadError> {
+    let (mut headers, _) = BlobHeaders::<E>::read_in::<P, B>(blob, byte_order)?;
+    let body_sections = headers.body_sections::<P, B>();
+    let new_byte_order = byte_order.opposite();
+    let toc = headers.toc.clone();
+    let section_range = |kind: SectionKind, raw_size: usize| {
+        let offset = toc.entry(kind).map_or(0, |entry| entry.offset as usize);
+        offset..offset + raw_size
+    };
+
+    // Reverse each integer of the body sections
+    // The sections unknown to this version are copied as they are.
+    new_blob.copy_from_slice(blob);
+    body_sections.iter().for_each(|section_size| {
+        new_blob[section_range(section_size.kind, section_size.raw_size)]
+            .chunks_exact_mut(section_size.item_size)
+            .for_each(|item| item.reverse());
+    });
+
+    // Headers in the new byte order
+    if new_byte_order != ByteOrder::NATIVE {
+        headers.swap_byte_order();
+    }
+    headers.magic_number.set_byte_order(new_byte_order);
+    headers.checksum_header = Some(ChecksumHeader::default());
+    headers.write_to_blob(new_blob);
+
+    // Checksums of the converted sections
+    let mut checksum_header = ChecksumHeader::default();
+    let toc_range = PREFIX_SIZE..PREFIX_SIZE + headers.toc.raw_size();
+    let header_checksums = [
+        ChecksumHeader::checksum_of_chain(&[
+            headers.magic_number.as_bytes(),
+            headers.type_descriptor.as_bytes(),
+            &new_blob[toc_range],
+        ]),
+        ChecksumHeader::checksum_of(headers.text_encoder.as_bytes()),
+        ChecksumHeader::checksum_of(headers.count_array_header.as_bytes()),
+        ChecksumHeader::checksum_of(headers.suffix_array_header.as_bytes()),
+        ChecksumHeader::checksum_of(headers.bwm_header.as_bytes()),
+    ];
+    let body_checksums = body_sections.map(|section_size| {
+        ChecksumHeader::checksum_of(&new_blob[section_range(section_size.kind, section_size.raw_size)])
+    });
+    // The metadata is copied as it is.
+    let metadata_checksum = headers.metadata(new_blob).map_or(0, ChecksumHeader::checksum_of);
+    checksum_header.sections_mut().into_iter()
+        .zip(header_checksums.into_iter().chain(body_checksums).chain([metadata_checksum]))
+        .for_each(|((_, checksum), computed)| *checksum = computed);
+    if new_byte_order != ByteOrder::NATIVE {
+        checksum_header.swap_byte_order();
+    }
+    headers.checksum_header = Some(checksum_header);
+
+    headers.write_to_blob(new_blob);
+    Ok(new_byte_order)
+}
@@ -0,0 +1,41 @@
+/// Embed the blob file in the binary with the alignment of every block
+///
+/// `include_bytes!` gives the bytes aligned to 1 byte, which `FmIndex::load` rejects.
+/// This macro gives `&'static [u8]` aligned to `AlignedBlob::MAX_BLOCK_ALIGN`,
+/// which is loaded with `FmIndex::load` or `OwnedFmIndex::load`.
+///
+/// The magic number of the blob is checked at compile time,
+/// so that the blob of the other byte order or an unsupported version fails to compile.
+///
+/// ```ignore
+/// use sview_fmindex::{FmIndex, include_blob, blocks::Block2, text_encoders::EncodingTable};
+///
+/// static ADAPTERS: &[u8] = include_blob!("../adapters.blob");
+///
+/// let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(ADAPTERS).unwrap();
+/// ```
+#[macro_export]
+macro_rules! include_blob {
+    ($path:expr $(,)?) => {{
+        const _: () = $crate::__private::assert_blob_prefix(::core::include_bytes!($path));
+        static BLOB: &$crate::__private::Aligned<[u8]> = &$crate::__private::Aligned(*::core::include_bytes!($path));
+        &BLOB.0
+    }};
+}
+
+// Used by the macro
+#[doc(hidden)]
+pub mod __private {
+    use crate::components::MagicNumber;
+
+    // Alignment of `AlignedBlob::MAX_BLOCK_ALIGN`
+    #[repr(C, align(16))]
+    pub struct Aligned<T: ?Sized>(pub T);
+    const _: () = assert!(align_of::<Aligned<[u8; 0]>>() == crate::AlignedBlob::MAX_BLOCK_ALIGN);
+
+    pub const fn assert_blob_prefix(blob: &[u8]) {
+        if let Err(message) = MagicNumber::check_prefix(blob) {
+            panic!("{}", message);
+        }
+    }
+}
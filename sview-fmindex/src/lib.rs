@@ -1,12 +1,24 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 // Core types
 mod text_length;
 pub use text_length::Position;
 // Header & View to compose FmIndex
 mod components;
-pub use components::{TextEncoder, text_encoders, Block, blocks};
+pub use components::{TextEncoder, text_encoders, Block, blocks, TypeDescriptor, ByteOrder};
 // Builder for FmIndex
+#[cfg(feature = "std")]
 mod builder;
-pub use builder::{FmIndexBuilder, BuildError, build_config};
+#[cfg(feature = "std")]
+pub use builder::{FmIndexBuilder, BuildError, build_config, BuildPhase, ProgressSink, CancellationToken};
+// Blob allocation aligned for the blocks
+mod aligned_blob;
+pub use aligned_blob::AlignedBlob;
+// Blob embedded in the binary
+mod include_blob;
+#[doc(hidden)]
+pub use include_blob::__private;
 
 /// FM-index
 ///
@@ -17,10 +29,15 @@ pub struct FmIndex<'a, P: Position, B: Block, E: TextEncoder> {
     source_blob: &'a [u8],
     // headers
     magic_number: components::MagicNumber,
+    type_descriptor: components::TypeDescriptor,
+    toc: components::Toc,
     text_encoder: E,
     count_array_header: components::CountArrayHeader,
     suffix_array_header: components::SuffixArrayHeader,
     bwm_header: components::BwmHeader,
+    checksum_header: Option<components::ChecksumHeader>,
+    // user-defined data
+    metadata: Option<&'a [u8]>,
     // views
     count_array_view: components::CountArrayView<'a, P>,
     suffix_array_view: components::SuffixArrayView<'a, P>,
@@ -33,10 +50,44 @@ mod load_from_blob;
 pub use load_from_blob::LoadError;
 //  - Count & locate pattern
 mod locate;
+pub use locate::LocateIter;
+//  - Verify checksums
+mod verify;
+pub use verify::VerifyError;
+//  - Inspect blob without types
+mod blob_info;
+pub use blob_info::{BlobInfo, SectionInfo};
+//  - Upgrade blob of previous format versions
+mod upgrade_blob;
+pub use upgrade_blob::{upgrade_blob, upgraded_blob_size};
+//  - Convert byte order of blob
+mod convert_endianness;
+pub use convert_endianness::convert_endianness;
 //  - Reference to source blob data
 mod reference_to_source_blob;
 //  - Get debug info
 // mod debug;
 
-#[cfg(test)]
+// FM-index with runtime types
+mod any_fm_index;
+pub use any_fm_index::AnyFmIndex;
+// FM-index owning its blob
+mod owned_fm_index;
+pub use owned_fm_index::{OwnedFmIndex, StableBlob};
+// FM-index on memory-mapped file
+#[cfg(feature = "std")]
+mod mapped_fm_index;
+#[cfg(feature = "std")]
+pub use mapped_fm_index::{MappedFmIndex, MmapOptions, AccessPattern, HotSections, OpenError};
+// FM-index reading the blob through a source
+#[cfg(feature = "std")]
+mod blob_source;
+#[cfg(feature = "std")]
+pub use blob_source::{BlobSource, FileSource, CachedSource, CacheStats};
+#[cfg(feature = "std")]
+mod sourced_fm_index;
+#[cfg(feature = "std")]
+pub use sourced_fm_index::SourcedFmIndex;
+
+#[cfg(all(test, feature = "std"))]
 mod tests;
@@ -1,11 +1,14 @@
+use core::ops::Range;
+use zerocopy::IntoBytes;
+
 use crate::{
     FmIndex,
     // traits
     Position, Block,
     components::{
-        Header, View,
+        Header, View, PrefixAlign, ByteOrder, SectionSize, Toc, SectionKind, PREFIX_SIZE, header_section,
         // headers
-        MagicNumber, TextEncoder, CountArrayHeader, SuffixArrayHeader, BwmHeader,
+        MagicNumber, TypeDescriptor, TextEncoder, CountArrayHeader, SuffixArrayHeader, BwmHeader, ChecksumHeader,
         // views
         CountArrayView, SuffixArrayView, BwmView,
     },
@@ -21,66 +24,438 @@ pub enum LoadError {
     /// The size of the provided blob does not match the expected size calculated from its headers.
     #[error("Mismatched blob size: headers indicate a total size of {0} bytes, but the provided blob is {1} bytes.")]
     MismatchedBlobSize(usize, usize),
+
+    /// The blob was built with different position, block, vector or text encoder types.
+    #[error("Mismatched index type: expected {expected}, but the blob was built as {found}.")]
+    TypeMismatch {
+        expected: TypeDescriptor,
+        found: TypeDescriptor,
+    },
+
+    /// The blob was built with a combination of types that is not supported.
+    #[error("Unsupported index type: {0} is not a supported combination.")]
+    UnsupportedType(TypeDescriptor),
+
+    /// The blob was built on a target with the other byte order.
+    #[error("Mismatched byte order: expected {expected}, but the blob was built as {found}. Convert the blob with `convert_endianness`.")]
+    ByteOrderMismatch {
+        expected: ByteOrder,
+        found: ByteOrder,
+    },
+
+    /// The blob of format version 0.0 does not record the types that it was built with.
+    #[error("Untyped blob: the blob of format version 0.0 does not record its types. Load or upgrade it with the types it was built with.")]
+    UntypedBlob,
+
+    /// The operation requires the blob of the current format version.
+    #[error("Outdated format version: the blob is of version {major}.{minor}. Upgrade it with `upgrade_blob` first.")]
+    OutdatedVersion {
+        major: u8,
+        minor: u8,
+    },
+
+    /// The blob ends before all headers could be read.
+    #[error("Truncated header: the blob ends before all headers could be read.")]
+    TruncatedHeader,
+
+    /// The blob is not aligned to the alignment of the block type.
+    #[error("Misaligned blob: required alignment is {required} bytes, but the blob has an offset of {offset} bytes.")]
+    MisalignedBlob {
+        required: usize,
+        offset: usize,
+    },
+
+    /// A header or body section of the blob is inconsistent.
+    #[error("Invalid section: the {0} of the blob is inconsistent.")]
+    InvalidSection(&'static str),
+
+    /// The table of contents lists a section that must be understood, but is unknown to this version.
+    #[error("Unknown required section: the section of kind {0} is required to read the blob, but is not supported by this version.")]
+    UnknownRequiredSection(u32),
 }
 
 impl<'a, P: Position, B: Block, E: TextEncoder> FmIndex<'a, P, B, E> {
     /// Load fm-index from blob
+    ///
+    /// The blobs of the previous minor versions are also loaded.
     pub fn load(blob: &'a [u8]) -> Result<Self, LoadError> {
-        // Load headers
-        let (magic_number, remaining_bytes) = MagicNumber::read_from_blob::<B>(blob);
-        if !(magic_number.is_valid() && magic_number.is_supported_version()) {
-            return Err(LoadError::InvalidFormat);
-        }
-        let (text_encoder, remaining_bytes) = E::read_from_blob::<B>(remaining_bytes);
-        let (count_array_header, remaining_bytes) = CountArrayHeader::read_from_blob::<B>(remaining_bytes);
-        let (suffix_array_header, remaining_bytes) = SuffixArrayHeader::read_from_blob::<B>(remaining_bytes);
-        let (bwm_header, body_blob) = BwmHeader::read_from_blob::<B>(remaining_bytes);
-
-        // check body size
-        let actual_body_size = body_blob.len();
-        let expected_body_size = {
-            CountArrayView::<P>::aligned_body_size::<B>(&count_array_header)
-            + SuffixArrayView::<P>::aligned_body_size::<B>(&suffix_array_header)
-            + BwmView::<P, B>::aligned_body_size::<B>(&bwm_header)
-        };
-        if actual_body_size != expected_body_size {
-            let header_size = {
-                magic_number.aligned_size::<B>()
-                + text_encoder.aligned_size::<B>()
-                + count_array_header.aligned_size::<B>()
-                + suffix_array_header.aligned_size::<B>()
-                + bwm_header.aligned_size::<B>()
-            };
-            return Err(LoadError::MismatchedBlobSize(
-                header_size + expected_body_size,
-                header_size + actual_body_size,
-            ));
+        // Check alignment
+        let required_alignment = B::ALIGN_SIZE;
+        let offset = blob.as_ptr() as usize % required_alignment;
+        if offset != 0 {
+            return Err(LoadError::MisalignedBlob { required: required_alignment, offset });
         }
 
+        // Load headers
+        let (headers, [count_array_body, suffix_array_body, bwm_body]) = BlobHeaders::<E>::read::<P, B>(blob)?;
+        let metadata = headers.metadata(blob);
+        let BlobHeaders {
+            magic_number,
+            type_descriptor,
+            toc,
+            text_encoder,
+            count_array_header,
+            suffix_array_header,
+            bwm_header,
+            checksum_header,
+        } = headers;
+
         // Get views
-        //  - Count array
-        let mut body_start_index = 0;
-        let mut body_end_index = CountArrayView::<P>::aligned_body_size::<B>(&count_array_header);
-        let count_array_view = CountArrayView::<P>::load_from_body::<B>(&count_array_header, &body_blob[body_start_index..body_end_index]);
-        //  - Suffix array
-        body_start_index = body_end_index;
-        body_end_index += SuffixArrayView::<P>::aligned_body_size::<B>(&suffix_array_header);
-        let suffix_array_view = SuffixArrayView::<P>::load_from_body::<B>(&suffix_array_header, &body_blob[body_start_index..body_end_index]);
-        //  - BWM
-        body_start_index = body_end_index;
-        body_end_index += BwmView::<P, B>::aligned_body_size::<B>(&bwm_header);
-        let bwm_view = BwmView::<P, B>::load_from_body::<B>(&bwm_header, &body_blob[body_start_index..body_end_index]);
+        let count_array_view = CountArrayView::<P>::load_from_body::<B>(&count_array_header, count_array_body)?;
+        let suffix_array_view = SuffixArrayView::<P>::load_from_body::<B>(&suffix_array_header, suffix_array_body)?;
+        let bwm_view = BwmView::<P, B>::load_from_body::<B>(&bwm_header, bwm_body)?;
 
         Ok(Self {
             source_blob: blob,
             magic_number,
+            type_descriptor,
+            toc,
             text_encoder,
             count_array_header,
             suffix_array_header,
             bwm_header,
+            checksum_header,
+            metadata,
             count_array_view,
             suffix_array_view,
             bwm_view,
         })
     }
-}
\ No newline at end of file
+}
+
+/// Headers read from blob
+pub(crate) struct BlobHeaders<E: TextEncoder> {
+    pub magic_number: MagicNumber,
+    pub type_descriptor: TypeDescriptor,
+    /// Derived from the headers for the version 0.0
+    pub toc: Toc,
+    pub text_encoder: E,
+    pub count_array_header: CountArrayHeader,
+    pub suffix_array_header: SuffixArrayHeader,
+    pub bwm_header: BwmHeader,
+    /// `None` for the version 0.0
+    pub checksum_header: Option<ChecksumHeader>,
+}
+
+/// Bodies of the count array, suffix array and BWM views
+pub(crate) type ViewBodies<'a> = [&'a [u8]; 3];
+
+/// Read the magic number and type descriptor, which do not depend on the byte order
+///
+/// The blobs of version 0.0 are rejected, since the types are not recorded.
+pub(crate) fn read_prefix(blob: &[u8]) -> Result<(MagicNumber, TypeDescriptor, &[u8]), LoadError> {
+    let (magic_number, remaining_bytes) = read_magic_number(blob)?;
+    if magic_number.is_untyped_version() {
+        return Err(LoadError::UntypedBlob);
+    }
+    let (type_descriptor, remaining_bytes) = TypeDescriptor::read_from_blob::<PrefixAlign>(remaining_bytes)
+        .ok_or(LoadError::TruncatedHeader)?;
+    Ok((magic_number, type_descriptor, remaining_bytes))
+}
+
+fn read_magic_number(blob: &[u8]) -> Result<(MagicNumber, &[u8]), LoadError> {
+    let (magic_number, remaining_bytes) = MagicNumber::read_from_blob::<PrefixAlign>(blob)
+        .ok_or(LoadError::TruncatedHeader)?;
+    if !(magic_number.is_valid() && magic_number.is_supported_version()) {
+        return Err(LoadError::InvalidFormat);
+    }
+    Ok((magic_number, remaining_bytes))
+}
+
+impl<E: TextEncoder> BlobHeaders<E> {
+    /// Read the headers and return them with the bodies of the views
+    ///
+    /// The headers are copied, so the blob does not have to be aligned.
+    /// The sections listed in the TOC are checked to match the headers.
+    pub fn read<P: Position, B: Block>(blob: &[u8]) -> Result<(Self, ViewBodies<'_>), LoadError> {
+        Self::read_in::<P, B>(blob, ByteOrder::NATIVE)
+    }
+    /// Read the headers of the blob in the given byte order
+    ///
+    /// The headers are returned in the native byte order.
+    /// The layout of the previous minor versions is read by the features of the magic number.
+    pub fn read_in<P: Position, B: Block>(blob: &[u8], byte_order: ByteOrder) -> Result<(Self, ViewBodies<'_>), LoadError> {
+        let headers = Self::read_headers_in::<P, B>(blob, byte_order)?;
+        let view_bodies = headers.view_bodies::<P, B>(blob)?;
+
+        Ok((headers, view_bodies))
+    }
+    /// Read and check the headers, without the bodies
+    ///
+    /// `blob` has to hold the bytes from the start of the blob to the end of the last header.
+    /// The sections are checked by `view_ranges` with the size of the whole blob.
+    pub fn read_headers_in<P: Position, B: Block>(blob: &[u8], byte_order: ByteOrder) -> Result<Self, LoadError> {
+        let (magic_number, _) = read_magic_number(blob)?;
+        let is_untyped_version = magic_number.is_untyped_version();
+        let (type_descriptor, remaining_bytes) = if is_untyped_version {
+            // The magic number was aligned by the block, and the types are trusted
+            let remaining_bytes = blob.get(magic_number.aligned_size::<B>()..)
+                .ok_or(LoadError::TruncatedHeader)?;
+            (TypeDescriptor::of::<P, B, E>(), remaining_bytes)
+        } else {
+            read_prefix(blob).map(|(_, type_descriptor, remaining_bytes)| (type_descriptor, remaining_bytes))?
+        };
+        let found_byte_order = magic_number.byte_order().ok_or(LoadError::InvalidFormat)?;
+        if found_byte_order != byte_order {
+            return Err(LoadError::ByteOrderMismatch {
+                expected: byte_order,
+                found: found_byte_order,
+            });
+        }
+        let expected_type_descriptor = TypeDescriptor::of::<P, B, E>();
+        if type_descriptor != expected_type_descriptor {
+            return Err(LoadError::TypeMismatch {
+                expected: expected_type_descriptor,
+                found: type_descriptor,
+            });
+        }
+
+        let mut headers = if is_untyped_version {
+            Self::read_untyped_headers::<P, B>(remaining_bytes, magic_number)?
+        } else {
+            Self::read_listed_headers(blob, magic_number, type_descriptor)?
+        };
+        if byte_order != ByteOrder::NATIVE {
+            headers.swap_byte_order();
+        }
+        let Self { count_array_header, suffix_array_header, bwm_header, .. } = &headers;
+
+        // Check headers
+        if !count_array_header.is_valid(B::MAX_SYMBOL) {
+            return Err(LoadError::InvalidSection("count array header"));
+        }
+        if !suffix_array_header.is_valid() {
+            return Err(LoadError::InvalidSection("suffix array header"));
+        }
+        if !bwm_header.is_valid(count_array_header.symbol_count) {
+            return Err(LoadError::InvalidSection("bwm header"));
+        }
+
+        if is_untyped_version {
+            let header_start = blob.len() - remaining_bytes.len();
+            headers.toc = headers.untyped_toc::<P, B>(header_start);
+        }
+
+        Ok(headers)
+    }
+    // Headers at the offsets in the TOC
+    fn read_listed_headers(
+        blob: &[u8],
+        magic_number: MagicNumber,
+        type_descriptor: TypeDescriptor,
+    ) -> Result<Self, LoadError> {
+        let byte_order = magic_number.byte_order().ok_or(LoadError::InvalidFormat)?;
+        let toc = blob.get(PREFIX_SIZE..)
+            .and_then(|toc_blob| Toc::read_from_blob(toc_blob, byte_order))
+            .ok_or(LoadError::TruncatedHeader)?;
+
+        Ok(Self {
+            text_encoder: read_listed_header(blob, &toc, SectionKind::TextEncoder)?,
+            count_array_header: read_listed_header(blob, &toc, SectionKind::CountArrayHeader)?,
+            suffix_array_header: read_listed_header(blob, &toc, SectionKind::SuffixArrayHeader)?,
+            bwm_header: read_listed_header(blob, &toc, SectionKind::BwmHeader)?,
+            checksum_header: Some(read_listed_header(blob, &toc, SectionKind::ChecksumHeader)?),
+            magic_number,
+            type_descriptor,
+            toc,
+        })
+    }
+    // Headers one after another, in the layout of the version 0.0
+    fn read_untyped_headers<P: Position, B: Block>(
+        remaining_bytes: &[u8],
+        magic_number: MagicNumber,
+    ) -> Result<Self, LoadError> {
+        let (text_encoder, remaining_bytes) = E::read_from_blob::<B>(remaining_bytes)
+            .ok_or(LoadError::TruncatedHeader)?;
+        let (count_array_header, remaining_bytes) = CountArrayHeader::read_from_blob::<B>(remaining_bytes)
+            .ok_or(LoadError::TruncatedHeader)?;
+        let (suffix_array_header, remaining_bytes) = SuffixArrayHeader::read_from_blob::<B>(remaining_bytes)
+            .ok_or(LoadError::TruncatedHeader)?;
+        let (bwm_header, _) = BwmHeader::read_from_blob::<B>(remaining_bytes)
+            .ok_or(LoadError::TruncatedHeader)?;
+        Ok(Self {
+            magic_number,
+            type_descriptor: TypeDescriptor::of::<P, B, E>(),
+            toc: Toc::default(),
+            text_encoder,
+            count_array_header,
+            suffix_array_header,
+            bwm_header,
+            checksum_header: None,
+        })
+    }
+    // TOC of the sections laid out one after another from the `header_start`, in the layout of the version 0.0
+    fn untyped_toc<P: Position, B: Block>(&self, header_start: usize) -> Toc {
+        let header_sections = [
+            header_section::<B>(SectionKind::TextEncoder, &self.text_encoder),
+            header_section::<B>(SectionKind::CountArrayHeader, &self.count_array_header),
+            header_section::<B>(SectionKind::SuffixArrayHeader, &self.suffix_array_header),
+            header_section::<B>(SectionKind::BwmHeader, &self.bwm_header),
+        ];
+        let body_sections = self.body_sections::<P, B>().map(|section_size| {
+            (section_size.kind, section_size.raw_size, section_size.aligned_size)
+        });
+        Toc::from_sections(header_start, header_sections.into_iter().chain(body_sections))
+    }
+    // Check the TOC against the headers, and slice the bodies of the views
+    fn view_bodies<'b, P: Position, B: Block>(&self, blob: &'b [u8]) -> Result<ViewBodies<'b>, LoadError> {
+        let [count_array, suffix_array, bwm] = self.view_ranges::<P, B>(blob.len())?;
+        Ok([
+            view_body(blob, count_array, SectionKind::CountArray)?,
+            view_body(blob, suffix_array, SectionKind::SuffixArray)?,
+            view_body(blob, bwm, SectionKind::SentinelIndex)?,
+        ])
+    }
+    /// Check the TOC against the headers, and get the ranges of the view bodies in the blob
+    ///
+    /// The ranges of the count array, suffix array and BWM views are returned in order.
+    pub fn view_ranges<P: Position, B: Block>(&self, blob_size: usize) -> Result<[Range<u64>; 3], LoadError> {
+        // Every section is known or optional, listed once, and aligned
+        for (index, entry) in self.toc.entries().iter().enumerate() {
+            match SectionKind::from_code(entry.kind) {
+                Some(_) => {
+                    if self.toc.entries()[..index].iter().any(|previous| previous.kind == entry.kind) {
+                        return Err(LoadError::InvalidSection("table of contents"));
+                    }
+                },
+                None => {
+                    if entry.is_required() {
+                        return Err(LoadError::UnknownRequiredSection(entry.kind));
+                    }
+                },
+            }
+            if entry.offset % B::ALIGN_SIZE as u64 != 0 {
+                return Err(LoadError::InvalidSection("table of contents"));
+            }
+        }
+
+        // Sections do not overlap each other or the TOC
+        let mut entries = self.toc.entries().to_vec();
+        entries.sort_by_key(|entry| entry.offset);
+        let mut previous_end = if self.magic_number.is_untyped_version() {
+            0
+        } else {
+            (PREFIX_SIZE + self.toc.raw_size()) as u64
+        };
+        for entry in entries {
+            if entry.offset < previous_end {
+                return Err(LoadError::InvalidSection("table of contents"));
+            }
+            previous_end = entry.end();
+        }
+
+        // The blob ends at the last section
+        let expected_blob_size = usize::try_from(self.toc.aligned_end::<B>()).unwrap_or(usize::MAX);
+        if expected_blob_size != blob_size {
+            return Err(LoadError::MismatchedBlobSize(expected_blob_size, blob_size));
+        }
+
+        // The sections of each view are laid out one after another as the headers specify
+        let [
+            count_array, kmer_multiplier, kmer_count_table,
+            suffix_array,
+            sentinel_index, rank_checkpoints, blocks,
+        ] = self.body_sections::<P, B>();
+        Ok([
+            self.view_range(&[count_array, kmer_multiplier, kmer_count_table])?,
+            self.view_range(&[suffix_array])?,
+            self.view_range(&[sentinel_index, rank_checkpoints, blocks])?,
+        ])
+    }
+    fn view_range(&self, section_sizes: &[SectionSize]) -> Result<Range<u64>, LoadError> {
+        let mut body_start = None;
+        let mut offset = 0_u64;
+        for section_size in section_sizes {
+            let kind = section_size.kind;
+            let entry = self.toc.entry(kind).ok_or(LoadError::InvalidSection(kind.name()))?;
+            let start = *body_start.get_or_insert(entry.offset);
+            if entry.offset != start.saturating_add(offset) || entry.length != section_size.raw_size as u64 {
+                return Err(LoadError::InvalidSection(kind.name()));
+            }
+            offset = offset.saturating_add(section_size.aligned_size as u64);
+        }
+        let start = body_start.unwrap_or(0);
+        Ok(start..start.saturating_add(offset))
+    }
+    /// Reverse the byte order of each header
+    ///
+    /// The byte order in the magic number is not changed.
+    /// The TOC is always kept in the native byte order.
+    pub fn swap_byte_order(&mut self) {
+        self.magic_number.swap_byte_order();
+        self.type_descriptor.swap_byte_order();
+        self.text_encoder.swap_byte_order();
+        self.count_array_header.swap_byte_order();
+        self.suffix_array_header.swap_byte_order();
+        self.bwm_header.swap_byte_order();
+        if let Some(checksum_header) = &mut self.checksum_header {
+            checksum_header.swap_byte_order();
+        }
+    }
+    /// Write the headers at the offsets in the TOC
+    ///
+    /// The TOC is written in the byte order of the magic number.
+    /// The TOC must have the layout of the current version, and the checksum header must be present.
+    pub fn write_to_blob(&self, blob: &mut [u8]) {
+        self.magic_number.write_to_blob(blob);
+        self.type_descriptor.write_to_blob(&mut blob[self.magic_number.aligned_size::<PrefixAlign>()..]);
+        let byte_order = self.magic_number.byte_order().unwrap_or(ByteOrder::NATIVE);
+        self.toc.write_to_blob(&mut blob[PREFIX_SIZE..], byte_order);
+
+        let mut write_header = |kind: SectionKind, bytes: &[u8]| {
+            if let Some(entry) = self.toc.entry(kind) {
+                let offset = entry.offset as usize;
+                blob[offset..offset + bytes.len()].copy_from_slice(bytes);
+            }
+        };
+        write_header(SectionKind::TextEncoder, self.text_encoder.as_bytes());
+        write_header(SectionKind::CountArrayHeader, self.count_array_header.as_bytes());
+        write_header(SectionKind::SuffixArrayHeader, self.suffix_array_header.as_bytes());
+        write_header(SectionKind::BwmHeader, self.bwm_header.as_bytes());
+        if let Some(checksum_header) = &self.checksum_header {
+            write_header(SectionKind::ChecksumHeader, checksum_header.as_bytes());
+        }
+    }
+    /// TOC of the headers in the layout of the current version
+    pub fn current_toc<P: Position, B: Block>(&self, metadata_len: Option<usize>) -> Toc {
+        Toc::of_current_layout::<P, B, E>(
+            &self.text_encoder,
+            &self.count_array_header,
+            &self.suffix_array_header,
+            &self.bwm_header,
+            metadata_len,
+        )
+    }
+    /// Metadata section listed in the TOC
+    ///
+    /// The blob must have been checked by `read`.
+    pub fn metadata<'b>(&self, blob: &'b [u8]) -> Option<&'b [u8]> {
+        self.toc.entry(SectionKind::Metadata)
+            .map(|entry| &blob[entry.offset as usize..entry.end() as usize])
+    }
+    /// Size of each body section, in the order of the blob
+    pub fn body_sections<P: Position, B: Block>(&self) -> [SectionSize; 7] {
+        let [count_array, kmer_multiplier, kmer_count_table] = self.count_array_header.body_sections::<P, B>();
+        let [suffix_array] = self.suffix_array_header.body_sections::<P, B>();
+        let [sentinel_index, rank_checkpoints, blocks] = self.bwm_header.body_sections::<P, B, B>();
+        [count_array, kmer_multiplier, kmer_count_table, suffix_array, sentinel_index, rank_checkpoints, blocks]
+    }
+}
+
+// Body in the range of the blob
+fn view_body(blob: &[u8], range: Range<u64>, first_kind: SectionKind) -> Result<&[u8], LoadError> {
+    usize::try_from(range.start).ok()
+        .zip(usize::try_from(range.end).ok())
+        .and_then(|(start, end)| blob.get(start..end))
+        .ok_or(LoadError::InvalidSection(first_kind.name()))
+}
+
+// Header at the offset of the TOC entry
+fn read_listed_header<H: Header>(blob: &[u8], toc: &Toc, kind: SectionKind) -> Result<H, LoadError> {
+    let entry = toc.entry(kind).ok_or(LoadError::InvalidSection(kind.name()))?;
+    let bytes = usize::try_from(entry.offset).ok()
+        .zip(usize::try_from(entry.end()).ok())
+        .and_then(|(start, end)| blob.get(start..end))
+        .ok_or(LoadError::TruncatedHeader)?;
+    H::read_from_bytes(bytes).map_err(|_| LoadError::InvalidSection(kind.name()))
+}
@@ -0,0 +1,52 @@
+use core::iter::FusedIterator;
+
+use super::{FmIndex, Position, Block, TextEncoder, Locations, infallible};
+use crate::components::{BwmView, SuffixArrayView};
+
+/// Lazy iterator of the locations, returned by `FmIndex::locate_iter`
+///
+/// Each location is resolved when it is taken, by LF mapping to the sampled suffix array,
+/// so that the locations are never collected at once.
+/// The locations come in the same order as `FmIndex::locate`, and from the back with `rev`.
+pub struct LocateIter<'s, 'a, P: Position, B: Block, E: TextEncoder>(
+    Locations<'s, 'a, P, E, BwmView<'a, P, B>, SuffixArrayView<'a, P>>,
+);
+
+impl<'a, P: Position, B: Block, E: TextEncoder> FmIndex<'a, P, B, E> {
+    /// Locate the occurrences with the pattern lazily
+    pub fn locate_iter(&self, pattern: &[u8]) -> LocateIter<'_, 'a, P, B, E> {
+        let query_sections = self.query_sections();
+        let pos_range = infallible(query_sections.get_pos_range(pattern));
+        LocateIter(query_sections.into_locations(pos_range))
+    }
+    /// Locate the occurrences with the reverse iterator of pattern lazily
+    pub fn locate_iter_from_rev_iter<I: Iterator<Item = u8>>(&self, pattern_rev_iter: I) -> LocateIter<'_, 'a, P, B, E> {
+        let query_sections = self.query_sections();
+        let pos_range = infallible(query_sections.get_pos_range_from_rev_iter(pattern_rev_iter));
+        LocateIter(query_sections.into_locations(pos_range))
+    }
+}
+
+impl<P: Position, B: Block, E: TextEncoder> Iterator for LocateIter<'_, '_, P, B, E> {
+    type Item = P;
+
+    #[inline]
+    fn next(&mut self) -> Option<P> {
+        self.0.next().map(infallible)
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<P: Position, B: Block, E: TextEncoder> DoubleEndedIterator for LocateIter<'_, '_, P, B, E> {
+    #[inline]
+    fn next_back(&mut self) -> Option<P> {
+        self.0.next_back().map(infallible)
+    }
+}
+
+impl<P: Position, B: Block, E: TextEncoder> ExactSizeIterator for LocateIter<'_, '_, P, B, E> {}
+
+impl<P: Position, B: Block, E: TextEncoder> FusedIterator for LocateIter<'_, '_, P, B, E> {}
@@ -1,46 +1,190 @@
+use alloc::vec::Vec;
+use core::convert::Infallible;
+
 use super::{FmIndex, Position, Block, TextEncoder};
+use crate::components::{
+    CountArrayView, BwmView, SuffixArrayView,
+    // traits
+    BwmItems, SuffixArrayItems,
+};
 
 mod with_slice;
 mod with_rev_iter;
+mod locate_iter;
+pub use locate_iter::LocateIter;
 
-impl<'a, P: Position, B: Block, E: TextEncoder> FmIndex<'a, P, B, E> {
-    fn get_locations(&self, pos_range: (P, P)) -> Vec<P> {
+/// Sections read by the queries
+///
+/// `FmIndex` reads the slices of the blob, which never fails.
+/// Other storages, such as the blob source, can fail to read the BWM and suffix array.
+pub(crate) struct QuerySections<'s, 'a, P: Position, E: TextEncoder, W, S> {
+    pub text_encoder: &'s E,
+    pub count_array_view: &'s CountArrayView<'a, P>,
+    pub bwm: &'s W,
+    pub suffix_array: &'s S,
+}
+
+impl<'s, 'a, P, E, W, S> QuerySections<'s, 'a, P, E, W, S> where
+    P: Position,
+    E: TextEncoder,
+    W: BwmItems<P>,
+    S: SuffixArrayItems<P, Error = W::Error>,
+{
+    pub fn get_locations(&self, pos_range: (P, P)) -> Result<Vec<P>, W::Error> {
         let mut locations: Vec<P> = Vec::with_capacity((pos_range.1 - pos_range.0).as_usize());
 
-        self.write_locations_to_buffer(pos_range, &mut locations);
-        locations
+        self.write_locations_to_buffer(pos_range, &mut locations)?;
+        Ok(locations)
     }
     #[inline]
-    fn write_locations_to_buffer(
+    pub fn write_locations_to_buffer(
         &self,
         pos_range: (P, P),
         locations: &mut Vec<P>,
-    ) {
-        'each_pos: for mut pos in P::as_vec_in_range(&pos_range.0, &pos_range.1) {
-            let mut offset: P = P::ZERO;
-            while pos % self.suffix_array_view.sampling_ratio() != P::ZERO { 
-                match self.bwm_view.get_pre_rank_and_symidx(pos) {
-                    Some((rank, symidx)) => {
-                        let precount = self.count_array_view.get_precount(symidx as usize);
-                        pos = precount + rank;
-                    },
-                    None => { // if position == pidx
-                        locations.push(offset);
-                        continue 'each_pos;
-                    }
+    ) -> Result<(), W::Error> {
+        let (mut pos, end) = pos_range;
+        while pos < end {
+            locations.push(self.get_location(pos)?);
+            pos += P::ONE;
+        }
+        Ok(())
+    }
+    // Location of the suffix array position, by LF mapping to the sampled position
+    #[inline]
+    pub fn get_location(&self, mut pos: P) -> Result<P, W::Error> {
+        let mut offset: P = P::ZERO;
+        while pos % self.suffix_array.sampling_ratio() != P::ZERO {
+            match self.bwm.get_pre_rank_and_symidx(pos)? {
+                Some((rank, symidx)) => {
+                    let precount = self.count_array_view.get_precount(symidx as usize);
+                    pos = precount + rank;
+                },
+                None => { // if position == pidx
+                    return Ok(offset);
                 }
-                offset += P::ONE;
             }
-            let location = self.suffix_array_view.get_location_of(pos) + offset;
-            locations.push(location);
+            offset += P::ONE;
+        }
+        Ok(self.suffix_array.get_location_of(pos)? + offset)
+    }
+    // Locations of the position range, resolved on demand
+    pub fn into_locations(self, pos_range: (P, P)) -> Locations<'s, 'a, P, E, W, S> {
+        Locations { query_sections: self, pos_range }
+    }
+    // Get the position range of the text
+    pub fn get_pos_range(&self, pattern: &[u8]) -> Result<(P, P), W::Error> {
+        let (mut pos_range, mut idx) = self.count_array_view.get_initial_pos_range_and_idx_of_pattern(
+            pattern,
+            self.text_encoder,
+        );
+        // LF mapping
+        while pos_range.0 < pos_range.1 && idx > 0 {
+            idx -= 1;
+            let next_sym = pattern[idx];
+            pos_range = self.next_pos_range(pos_range, next_sym)?;
         }
+        Ok(pos_range)
+    }
+    pub fn get_pos_range_from_rev_iter<I: Iterator<Item = u8>>(
+        &self,
+        mut pattern_rev_iter: I,
+    ) -> Result<(P, P), W::Error> {
+        let mut pos_range = self.count_array_view.get_initial_pos_range_and_idx_of_pattern_rev_iter(
+            &mut pattern_rev_iter,
+            self.text_encoder,
+        );
+        // LF mapping
+        while pos_range.0 < pos_range.1  {
+            match pattern_rev_iter.next() {
+                Some(next_sym) => {
+                    pos_range = self.next_pos_range(pos_range, next_sym)?;
+                },
+                None => break,
+            };
+        }
+        Ok(pos_range)
     }
     #[inline]
-    fn next_pos_range(&self, pos_range: (P, P), sym: u8) -> (P, P) {
+    fn next_pos_range(&self, pos_range: (P, P), sym: u8) -> Result<(P, P), W::Error> {
         let symidx = self.text_encoder.idx_of(sym);
         let precount = self.count_array_view.get_precount(symidx as usize);
-        let start_rank = self.bwm_view.get_next_rank(pos_range.0, symidx);
-        let end_rank = self.bwm_view.get_next_rank(pos_range.1, symidx);
-        (precount + start_rank, precount + end_rank)
+        let start_rank = self.bwm.get_next_rank(pos_range.0, symidx)?;
+        let end_rank = self.bwm.get_next_rank(pos_range.1, symidx)?;
+        Ok((precount + start_rank, precount + end_rank))
+    }
+}
+
+/// Iterator of the locations in the position range
+///
+/// The range is narrowed from both ends, in the order of the suffix array.
+pub(crate) struct Locations<'s, 'a, P: Position, E: TextEncoder, W, S> {
+    query_sections: QuerySections<'s, 'a, P, E, W, S>,
+    pos_range: (P, P),
+}
+
+impl<P, E, W, S> Iterator for Locations<'_, '_, P, E, W, S> where
+    P: Position,
+    E: TextEncoder,
+    W: BwmItems<P>,
+    S: SuffixArrayItems<P, Error = W::Error>,
+{
+    type Item = Result<P, W::Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos_range.0 >= self.pos_range.1 {
+            return None;
+        }
+        let pos = self.pos_range.0;
+        self.pos_range.0 += P::ONE;
+        Some(self.query_sections.get_location(pos))
+    }
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.pos_range.1 - self.pos_range.0).as_usize();
+        (len, Some(len))
+    }
+}
+
+impl<P, E, W, S> DoubleEndedIterator for Locations<'_, '_, P, E, W, S> where
+    P: Position,
+    E: TextEncoder,
+    W: BwmItems<P>,
+    S: SuffixArrayItems<P, Error = W::Error>,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.pos_range.0 >= self.pos_range.1 {
+            return None;
+        }
+        self.pos_range.1 = self.pos_range.1 - P::ONE;
+        Some(self.query_sections.get_location(self.pos_range.1))
+    }
+}
+
+impl<P, E, W, S> ExactSizeIterator for Locations<'_, '_, P, E, W, S> where
+    P: Position,
+    E: TextEncoder,
+    W: BwmItems<P>,
+    S: SuffixArrayItems<P, Error = W::Error>,
+{}
+
+impl<'a, P: Position, B: Block, E: TextEncoder> FmIndex<'a, P, B, E> {
+    fn query_sections(&self) -> QuerySections<'_, 'a, P, E, BwmView<'a, P, B>, SuffixArrayView<'a, P>> {
+        QuerySections {
+            text_encoder: &self.text_encoder,
+            count_array_view: &self.count_array_view,
+            bwm: &self.bwm_view,
+            suffix_array: &self.suffix_array_view,
+        }
+    }
+}
+
+// The slices of the blob never fail to be read
+#[inline]
+fn infallible<T>(result: Result<T, Infallible>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(never) => match never {},
     }
 }
@@ -0,0 +1,228 @@
+use std::fs::File;
+use std::ops::Range;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::{
+    FmIndex, LoadError, OwnedFmIndex,
+    // traits
+    Position, Block, TextEncoder,
+    components::SectionKind,
+};
+
+/// FM-index loaded from the memory-mapped file
+///
+/// The pages are read from the file when the queries touch them,
+/// so that the index is ready as soon as the headers are read.
+/// The access to the pages is tuned with `MmapOptions`.
+///
+/// The file must not be modified while it is mapped.
+pub struct MappedFmIndex<P: Position + 'static, B: Block + 'static, E: TextEncoder> {
+    inner: OwnedFmIndex<Mmap, P, B, E>,
+}
+
+/// Options for the memory map of `MappedFmIndex`
+///
+/// The advices and locks are applied on Unix, and ignored on the other platforms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MmapOptions {
+    /// How the whole blob will be accessed
+    pub access_pattern: AccessPattern,
+    /// How the small sections read by every query are loaded
+    pub hot_sections: HotSections,
+    /// Exclude the blob from the core dumps (Linux only)
+    pub dont_dump: bool,
+}
+
+/// Access pattern of the whole blob
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AccessPattern {
+    /// No advice (the default read-ahead of the OS)
+    #[default]
+    Normal,
+    /// `MADV_RANDOM`: read only the touched pages, which fits the queries jumping around the blocks
+    Random,
+    /// `MADV_SEQUENTIAL`: read ahead aggressively
+    Sequential,
+    /// `MADV_WILLNEED`: read the whole blob in the background
+    WillNeed,
+}
+
+/// Loading of the hot sections: the count array, k-mer table, sentinel index and rank checkpoints
+///
+/// These sections are small and read by every query,
+/// while the large blocks and suffix array stay lazily paged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HotSections {
+    /// Read when the queries touch them
+    #[default]
+    Lazy,
+    /// `MADV_WILLNEED`: read in the background
+    Prefetch,
+    /// Read every page on open, so that the first queries do not wait for them
+    Warmup,
+    /// `mlock`: read on open and keep in memory
+    Lock,
+}
+
+/// Error type for opening `MappedFmIndex` or `SourcedFmIndex`
+#[derive(Debug, thiserror::Error)]
+pub enum OpenError {
+    /// Opening, mapping or reading the blob failed.
+    #[error("I/O error while opening the blob: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// The blob is not valid for the index type.
+    #[error(transparent)]
+    Load(#[from] LoadError),
+}
+
+impl MmapOptions {
+    /// For few queries in the memory constrained environment
+    ///
+    /// Only the touched pages are read.
+    pub fn lazy() -> Self {
+        Self {
+            access_pattern: AccessPattern::Random,
+            hot_sections: HotSections::Lazy,
+            dont_dump: false,
+        }
+    }
+    /// For the low latency of the first queries
+    ///
+    /// The hot sections are read on open, and the other pages are read when touched.
+    pub fn low_latency() -> Self {
+        Self {
+            access_pattern: AccessPattern::Random,
+            hot_sections: HotSections::Warmup,
+            dont_dump: false,
+        }
+    }
+    /// For many queries in batch
+    ///
+    /// The whole blob is read in the background.
+    pub fn batch() -> Self {
+        Self {
+            access_pattern: AccessPattern::WillNeed,
+            hot_sections: HotSections::Warmup,
+            dont_dump: false,
+        }
+    }
+}
+
+impl<P: Position + 'static, B: Block + 'static, E: TextEncoder> MappedFmIndex<P, B, E> {
+    /// Map the file and load the fm-index from it
+    pub fn open(path: impl AsRef<Path>, options: MmapOptions) -> Result<Self, OpenError> {
+        let file = File::open(path)?;
+        // SAFETY: the file must not be modified while it is mapped, as documented.
+        let mmap = unsafe { Mmap::map(&file)? };
+        let inner = OwnedFmIndex::load(mmap)?;
+
+        let mapped_fm_index = Self { inner };
+        mapped_fm_index.apply_options(options)?;
+        Ok(mapped_fm_index)
+    }
+
+    /// The fm-index borrowing the memory map
+    pub fn index(&self) -> &FmIndex<'_, P, B, E> {
+        self.inner.index()
+    }
+    /// Owned index, which can be moved across threads and stored without the options
+    pub fn into_owned(self) -> OwnedFmIndex<Mmap, P, B, E> {
+        self.inner
+    }
+    /// Read every page of the hot sections
+    pub fn warmup(&self) {
+        self.hot_section_ranges().into_iter().for_each(|range| {
+            touch_pages(&self.mmap()[range]);
+        });
+    }
+    /// Read every page of the blob
+    pub fn warmup_all(&self) {
+        touch_pages(self.mmap());
+    }
+
+    fn apply_options(&self, options: MmapOptions) -> std::io::Result<()> {
+        #[cfg(unix)]
+        {
+            use memmap2::Advice;
+            match options.access_pattern {
+                AccessPattern::Normal => {},
+                AccessPattern::Random => self.mmap().advise(Advice::Random)?,
+                AccessPattern::Sequential => self.mmap().advise(Advice::Sequential)?,
+                AccessPattern::WillNeed => self.mmap().advise(Advice::WillNeed)?,
+            }
+            #[cfg(target_os = "linux")]
+            if options.dont_dump {
+                self.mmap().advise(Advice::DontDump)?;
+            }
+        }
+        match options.hot_sections {
+            HotSections::Lazy => {},
+            HotSections::Prefetch => {
+                #[cfg(unix)]
+                self.hot_section_ranges().into_iter().try_for_each(|range| {
+                    self.mmap().advise_range(memmap2::Advice::WillNeed, range.start, range.len())
+                })?;
+            },
+            HotSections::Warmup => self.warmup(),
+            HotSections::Lock => {
+                #[cfg(unix)]
+                self.hot_section_ranges().into_iter().try_for_each(|range| {
+                    lock_pages(&self.mmap()[range])
+                })?;
+                #[cfg(not(unix))]
+                self.warmup();
+            },
+        }
+        Ok(())
+    }
+    fn mmap(&self) -> &Mmap {
+        self.inner.storage()
+    }
+    // Ranges of the hot sections in blob
+    fn hot_section_ranges(&self) -> Vec<Range<usize>> {
+        [
+            SectionKind::CountArray,
+            SectionKind::KmerMultiplier,
+            SectionKind::KmerCountTable,
+            SectionKind::SentinelIndex,
+            SectionKind::RankCheckpoints,
+        ].into_iter()
+            .filter_map(|kind| self.index().toc.entry(kind))
+            .map(|entry| entry.offset as usize..entry.end() as usize)
+            .filter(|range| !range.is_empty())
+            .collect()
+    }
+}
+
+impl<P: Position + 'static, B: Block + 'static, E: TextEncoder> std::fmt::Debug for MappedFmIndex<P, B, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedFmIndex")
+            .field("type_descriptor", &self.index().type_descriptor)
+            .field("blob_size", &self.mmap().len())
+            .finish_non_exhaustive()
+    }
+}
+
+const PAGE_SIZE_TO_TOUCH: usize = 4096;
+
+// Read a byte of each page
+fn touch_pages(bytes: &[u8]) {
+    bytes.iter().step_by(PAGE_SIZE_TO_TOUCH).for_each(|byte| {
+        // SAFETY: the reference is valid, and the volatile read is not optimized out.
+        unsafe { std::ptr::read_volatile(byte) };
+    });
+}
+
+#[cfg(unix)]
+fn lock_pages(bytes: &[u8]) -> std::io::Result<()> {
+    // SAFETY: the range is in the memory map, and locking does not change the contents.
+    let result = unsafe { libc::mlock(bytes.as_ptr().cast(), bytes.len()) };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
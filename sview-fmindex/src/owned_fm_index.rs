@@ -0,0 +1,71 @@
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+
+use crate::{
+    FmIndex, LoadError,
+    // traits
+    Position, Block, TextEncoder,
+};
+
+/// FM-index that owns the storage of its blob
+///
+/// Unlike `FmIndex`, it does not borrow the blob,
+/// so it can be kept in long-lived structs, caches and maps.
+/// It is `Send + Sync + 'static` with the storages of `Vec<u8>`, `Box<[u8]>`, `Arc<[u8]>` and `memmap2::Mmap`.
+pub struct OwnedFmIndex<S: StableBlob, P: Position + 'static, B: Block + 'static, E: TextEncoder> {
+    // Declared before the storage, to be dropped first
+    fm_index: FmIndex<'static, P, B, E>,
+    storage: S,
+}
+
+/// Storage of the blob that keeps its bytes at the same address when it is moved
+///
+/// # Safety
+///
+/// `as_ref` must return the same bytes at the same address for as long as the storage lives,
+/// including after the storage is moved.
+pub unsafe trait StableBlob: AsRef<[u8]> {}
+
+// SAFETY: the bytes are in the heap allocation, which is not moved with the owner.
+unsafe impl StableBlob for Vec<u8> {}
+unsafe impl StableBlob for Box<[u8]> {}
+unsafe impl StableBlob for Arc<[u8]> {}
+// SAFETY: the pages of the memory map are not moved with the owner.
+#[cfg(feature = "std")]
+unsafe impl StableBlob for memmap2::Mmap {}
+// SAFETY: the bytes live forever.
+unsafe impl StableBlob for &'static [u8] {}
+
+impl<S: StableBlob, P: Position + 'static, B: Block + 'static, E: TextEncoder> OwnedFmIndex<S, P, B, E> {
+    /// Load fm-index from the blob in the storage
+    pub fn load(storage: S) -> Result<Self, LoadError> {
+        let blob = storage.as_ref();
+        // SAFETY: `StableBlob` keeps the bytes at the same address while the storage lives,
+        // and the index borrowing them is dropped before the storage.
+        let blob: &'static [u8] = unsafe { core::slice::from_raw_parts(blob.as_ptr(), blob.len()) };
+        let fm_index = FmIndex::load(blob)?;
+        Ok(Self { fm_index, storage })
+    }
+
+    /// The fm-index borrowing the storage
+    pub fn index(&self) -> &FmIndex<'_, P, B, E> {
+        &self.fm_index
+    }
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+    /// Drop the index and return the storage
+    pub fn into_storage(self) -> S {
+        let Self { fm_index, storage } = self;
+        drop(fm_index);
+        storage
+    }
+}
+
+impl<S: StableBlob, P: Position + 'static, B: Block + 'static, E: TextEncoder> core::fmt::Debug for OwnedFmIndex<S, P, B, E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("OwnedFmIndex")
+            .field("type_descriptor", &self.fm_index.type_descriptor)
+            .field("blob_size", &self.storage.as_ref().len())
+            .finish_non_exhaustive()
+    }
+}
@@ -9,4 +9,10 @@ impl<'a, P: Position, B: Block, E: TextEncoder> FmIndex<'a, P, B, E> {
     pub fn blob(&self) -> &'a [u8] {
         self.source_blob
     }
+    /// Returns the metadata set by `FmIndexBuilder::set_metadata`, borrowed from the blob.
+    ///
+    /// Returns `None` if the blob was built without metadata.
+    pub fn metadata(&self) -> Option<&'a [u8]> {
+        self.metadata
+    }
 }
\ No newline at end of file
@@ -0,0 +1,260 @@
+use std::io;
+use std::marker::PhantomData;
+
+use zerocopy::{FromBytes, IntoBytes};
+
+use crate::{
+    AlignedBlob, BlobSource, LoadError, OpenError,
+    // traits
+    Position, Block, TextEncoder,
+    components::{
+        View, ByteOrder, Toc, SectionKind, TypeDescriptor, PREFIX_SIZE,
+        CountArrayView, BwmItems, SuffixArrayItems,
+    },
+    load_from_blob::{BlobHeaders, read_prefix},
+    locate::QuerySections,
+};
+
+/// FM-index reading the blob through a `BlobSource`
+///
+/// The headers, count array and k-mer table are read on open,
+/// and each query reads only the rank checkpoints, blocks and suffix array items it touches.
+/// It fits the storages that can not be mapped, such as the FUSE-mounted object storage.
+/// Use `FmIndex` on the slice of blob whenever the blob can be mapped or loaded, as it is much faster.
+///
+/// The queries fail with `std::io::Error` when the source fails to read.
+/// Only the blobs of the current format version are opened.
+pub struct SourcedFmIndex<S: BlobSource, P: Position + 'static, B: Block + 'static, E: TextEncoder> {
+    type_descriptor: TypeDescriptor,
+    text_encoder: E,
+    // Declared before the body, to be dropped first
+    count_array_view: CountArrayView<'static, P>,
+    count_array_body: AlignedBlob,
+    items: SourcedItems<S, P, B>,
+}
+
+// Rank checkpoints, blocks and suffix array items read from the source
+struct SourcedItems<S: BlobSource, P: Position, B: Block> {
+    source: S,
+    symbol_with_sentinel_count: P,
+    sentinel_index: P,
+    sampling_ratio: P,
+    rank_checkpoints: Section,
+    blocks: Section,
+    suffix_array: Section,
+    _phantom: PhantomData<B>,
+}
+
+type SourcedQuerySections<'s, S, P, B, E> = QuerySections<'s, 'static, P, E, SourcedItems<S, P, B>, SourcedItems<S, P, B>>;
+
+// Offset and count of the items in the source
+#[derive(Clone, Copy)]
+struct Section {
+    offset: u64,
+    item_count: u64,
+}
+
+impl<S: BlobSource, P: Position + 'static, B: Block + 'static, E: TextEncoder> SourcedFmIndex<S, P, B, E> {
+    /// Read the headers and the count array from the source
+    pub fn open(source: S) -> Result<Self, OpenError> {
+        let blob_size = source.size()?;
+        let blob_size_usize = usize::try_from(blob_size).map_err(|_| LoadError::InvalidSection("table of contents"))?;
+
+        // Prefix and TOC
+        let mut prefix_bytes = vec![0; PREFIX_SIZE + Toc::HEADER_SIZE];
+        read_header_bytes(&source, blob_size, 0, &mut prefix_bytes)?;
+        // The blobs of version 0.0, without the TOC, are rejected as untyped.
+        let (magic_number, _, _) = read_prefix(&prefix_bytes)?;
+        let byte_order = magic_number.byte_order().unwrap_or(ByteOrder::NATIVE);
+        let toc_size = Toc::raw_size_in_blob(&prefix_bytes[PREFIX_SIZE..], byte_order)
+            .ok_or(LoadError::TruncatedHeader)?;
+        let mut toc_bytes = vec![0; toc_size];
+        read_header_bytes(&source, blob_size, PREFIX_SIZE as u64, &mut toc_bytes)?;
+        let toc = Toc::read_from_blob(&toc_bytes, byte_order).ok_or(LoadError::TruncatedHeader)?;
+
+        // Headers, from the start of the blob to the end of the last header
+        let header_end = [
+            SectionKind::TextEncoder,
+            SectionKind::CountArrayHeader,
+            SectionKind::SuffixArrayHeader,
+            SectionKind::BwmHeader,
+            SectionKind::ChecksumHeader,
+        ].into_iter()
+            .filter_map(|kind| toc.entry(kind).map(|entry| entry.end()))
+            .max()
+            .unwrap_or(0)
+            .max((PREFIX_SIZE + toc_size) as u64);
+        let mut header_bytes = vec![0; usize::try_from(header_end).map_err(|_| LoadError::TruncatedHeader)?];
+        read_header_bytes(&source, blob_size, 0, &mut header_bytes)?;
+        let headers = BlobHeaders::<E>::read_headers_in::<P, B>(&header_bytes, ByteOrder::NATIVE)?;
+        let [count_array_range, _, _] = headers.view_ranges::<P, B>(blob_size_usize)?;
+
+        // Count array and k-mer table
+        let mut count_array_body = AlignedBlob::for_block::<B>((count_array_range.end - count_array_range.start) as usize);
+        source.read_exact_at(count_array_range.start, &mut count_array_body)?;
+        // SAFETY: the heap allocation of the body is not moved with the index,
+        // and the view borrowing it is dropped before the body.
+        let body: &'static [u8] = unsafe { std::slice::from_raw_parts(count_array_body.as_ptr(), count_array_body.len()) };
+        let count_array_view = CountArrayView::<P>::load_from_body::<B>(&headers.count_array_header, body)?;
+
+        // Items read by the queries
+        let section = |kind: SectionKind, item_size: usize| -> Result<Section, LoadError> {
+            let entry = headers.toc.entry(kind).ok_or(LoadError::InvalidSection(kind.name()))?;
+            Ok(Section { offset: entry.offset, item_count: entry.length / item_size as u64 })
+        };
+        let sentinel_index_section = section(SectionKind::SentinelIndex, size_of::<P>())?;
+        let mut sentinel_index_bytes = vec![0; size_of::<P>()];
+        source.read_exact_at(sentinel_index_section.offset, &mut sentinel_index_bytes)?;
+        let sentinel_index = P::read_from_bytes(&sentinel_index_bytes)
+            .map_err(|_| LoadError::InvalidSection("sentinel index"))?;
+        let items = SourcedItems {
+            symbol_with_sentinel_count: P::from_u32(headers.bwm_header.symbol_count),
+            sentinel_index,
+            sampling_ratio: P::from_u32(headers.suffix_array_header.sampling_ratio),
+            rank_checkpoints: section(SectionKind::RankCheckpoints, size_of::<P>())?,
+            blocks: section(SectionKind::Blocks, size_of::<B>())?,
+            suffix_array: section(SectionKind::SuffixArray, size_of::<P>())?,
+            source,
+            _phantom: PhantomData,
+        };
+
+        Ok(Self {
+            type_descriptor: headers.type_descriptor,
+            text_encoder: headers.text_encoder,
+            count_array_view,
+            count_array_body,
+            items,
+        })
+    }
+
+    /// Count the number of occurrences with the pattern
+    pub fn count(&self, pattern: &[u8]) -> io::Result<P> {
+        let pos_range = self.query_sections().get_pos_range(pattern)?;
+        Ok(pos_range.1 - pos_range.0)
+    }
+    /// Locate all occurrences with the pattern
+    pub fn locate(&self, pattern: &[u8]) -> io::Result<Vec<P>> {
+        let query_sections = self.query_sections();
+        let pos_range = query_sections.get_pos_range(pattern)?;
+        query_sections.get_locations(pos_range)
+    }
+    /// Locate all occurrences with the pattern & write to buffer
+    pub fn locate_to_buffer(&self, pattern: &[u8], buffer: &mut Vec<P>) -> io::Result<()> {
+        let query_sections = self.query_sections();
+        let pos_range = query_sections.get_pos_range(pattern)?;
+        query_sections.write_locations_to_buffer(pos_range, buffer)
+    }
+    /// Count the number of occurrences with the reverse iterator of pattern
+    pub fn count_rev_iter<I: Iterator<Item = u8>>(&self, pattern_rev_iter: I) -> io::Result<P> {
+        let pos_range = self.query_sections().get_pos_range_from_rev_iter(pattern_rev_iter)?;
+        Ok(pos_range.1 - pos_range.0)
+    }
+    /// Locate all occurrences with the reverse iterator of pattern
+    pub fn locate_rev_iter<I: Iterator<Item = u8>>(&self, pattern_rev_iter: I) -> io::Result<Vec<P>> {
+        let query_sections = self.query_sections();
+        let pos_range = query_sections.get_pos_range_from_rev_iter(pattern_rev_iter)?;
+        query_sections.get_locations(pos_range)
+    }
+    /// Locate the occurrences with the pattern lazily
+    ///
+    /// Each location is read from the source when it is taken, in the same order as `locate`.
+    pub fn locate_iter(
+        &self,
+        pattern: &[u8],
+    ) -> io::Result<impl DoubleEndedIterator<Item = io::Result<P>> + ExactSizeIterator + use<'_, S, P, B, E>> {
+        let query_sections = self.query_sections();
+        let pos_range = query_sections.get_pos_range(pattern)?;
+        Ok(query_sections.into_locations(pos_range))
+    }
+    /// Locate the occurrences with the reverse iterator of pattern lazily
+    pub fn locate_iter_from_rev_iter<I: Iterator<Item = u8>>(
+        &self,
+        pattern_rev_iter: I,
+    ) -> io::Result<impl DoubleEndedIterator<Item = io::Result<P>> + ExactSizeIterator + use<'_, S, P, B, E, I>> {
+        let query_sections = self.query_sections();
+        let pos_range = query_sections.get_pos_range_from_rev_iter(pattern_rev_iter)?;
+        Ok(query_sections.into_locations(pos_range))
+    }
+
+    pub fn type_descriptor(&self) -> TypeDescriptor {
+        self.type_descriptor
+    }
+    pub fn source(&self) -> &S {
+        &self.items.source
+    }
+    pub fn into_source(self) -> S {
+        let Self { count_array_view, items, .. } = self;
+        drop(count_array_view);
+        items.source
+    }
+
+    fn query_sections(&self) -> SourcedQuerySections<'_, S, P, B, E> {
+        QuerySections {
+            text_encoder: &self.text_encoder,
+            count_array_view: &self.count_array_view,
+            bwm: &self.items,
+            suffix_array: &self.items,
+        }
+    }
+}
+
+impl<S: BlobSource, P: Position, B: Block> SourcedItems<S, P, B> {
+    // Read the item of the section
+    fn read_item<T: FromBytes + IntoBytes>(&self, section: Section, index: usize) -> io::Result<T> {
+        if index as u64 >= section.item_count {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "item index is out of the section"));
+        }
+        let mut item = T::new_zeroed();
+        let offset = section.offset + (index * size_of::<T>()) as u64;
+        self.source.read_exact_at(offset, item.as_mut_bytes())?;
+        Ok(item)
+    }
+}
+
+impl<S: BlobSource, P: Position, B: Block> BwmItems<P> for SourcedItems<S, P, B> {
+    type Block = B;
+    type Error = io::Error;
+
+    fn symbol_with_sentinel_count(&self) -> P {
+        self.symbol_with_sentinel_count
+    }
+    fn sentinel_index(&self) -> P {
+        self.sentinel_index
+    }
+    fn rank_checkpoint(&self, rank_idx: usize) -> io::Result<P> {
+        self.read_item(self.rank_checkpoints, rank_idx)
+    }
+    fn with_block<T>(&self, block_idx: usize, f: impl FnOnce(&B) -> T) -> io::Result<T> {
+        let block: B = self.read_item(self.blocks, block_idx)?;
+        Ok(f(&block))
+    }
+}
+
+impl<S: BlobSource, P: Position, B: Block> SuffixArrayItems<P> for SourcedItems<S, P, B> {
+    type Error = io::Error;
+
+    fn sampling_ratio(&self) -> P {
+        self.sampling_ratio
+    }
+    fn location(&self, index: usize) -> io::Result<P> {
+        self.read_item(self.suffix_array, index)
+    }
+}
+
+impl<S: BlobSource, P: Position + 'static, B: Block + 'static, E: TextEncoder> std::fmt::Debug for SourcedFmIndex<S, P, B, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SourcedFmIndex")
+            .field("type_descriptor", &self.type_descriptor)
+            .field("count_array_size", &self.count_array_body.len())
+            .finish_non_exhaustive()
+    }
+}
+
+// Read the bytes of the headers, which must be in the blob
+fn read_header_bytes(source: &impl BlobSource, blob_size: u64, offset: u64, buf: &mut [u8]) -> Result<(), OpenError> {
+    if offset.saturating_add(buf.len() as u64) > blob_size {
+        return Err(LoadError::TruncatedHeader.into());
+    }
+    source.read_exact_at(offset, buf)?;
+    Ok(())
+}
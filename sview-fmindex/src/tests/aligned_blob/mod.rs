@@ -0,0 +1,67 @@
+// Assert that the aligned blob satisfies the alignment of the blocks
+
+use crate::{
+    AlignedBlob, FmIndex, FmIndexBuilder, OwnedFmIndex,
+    blocks::Block3,
+    text_encoders::EncodingTable,
+};
+
+const TEXT: &[u8] = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA";
+
+type P = u64;
+type B = Block3<u128>;
+type E = EncodingTable;
+
+fn builder() -> FmIndexBuilder<P, B, E> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    FmIndexBuilder::new(
+        TEXT.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+}
+
+#[test]
+fn allocation_is_aligned() {
+    for align in [1, 8, 16, AlignedBlob::HUGE_PAGE_ALIGN] {
+        for len in [0, 1, 100, 4096] {
+            let blob = AlignedBlob::zeroed(len, align);
+            assert_eq!(blob.len(), len);
+            assert_eq!(blob.align(), align);
+            assert_eq!(blob.as_ptr() as usize % align, 0);
+            assert!(blob.iter().all(|byte| *byte == 0));
+
+            let cloned = blob.clone();
+            assert_eq!(cloned.as_ptr() as usize % align, 0);
+            assert_eq!(&cloned[..], &blob[..]);
+        }
+    }
+}
+
+#[test]
+fn built_blob_is_loaded_from_aligned_copies() {
+    let builder = builder();
+    let blob = builder.build_to_aligned_vec(TEXT.to_vec()).unwrap();
+    assert_eq!(blob.len(), builder.blob_size());
+
+    // Same as the blob built to the slice
+    let mut expected = AlignedBlob::for_block::<B>(builder.blob_size());
+    builder.build(TEXT.to_vec(), &mut expected).unwrap();
+    assert_eq!(&blob[..], &expected[..]);
+
+    // Copy from the misaligned slice
+    let mut misaligned = vec![0_u8; blob.len() + 16];
+    let start = (0..16).find(|start| (misaligned.as_ptr() as usize + start) % 16 != 0).unwrap();
+    misaligned[start..start + blob.len()].copy_from_slice(&blob);
+    let copied = AlignedBlob::copy_from_slice(&misaligned[start..start + blob.len()], AlignedBlob::MAX_BLOCK_ALIGN);
+    let fm_index = FmIndex::<P, B, E>::load(&copied).unwrap();
+    assert_eq!(fm_index.count(b"ACG"), 2);
+
+    // Read from the file
+    let path = std::env::temp_dir().join(format!("sview-fmindex-aligned-{}.blob", std::process::id()));
+    std::fs::write(&path, &blob[..]).unwrap();
+    let read = AlignedBlob::read_from_path(&path, AlignedBlob::MAX_BLOCK_ALIGN).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let owned_fm_index = OwnedFmIndex::<_, P, B, E>::load(read).unwrap();
+    assert_eq!(owned_fm_index.index().count(b"ACG"), 2);
+}
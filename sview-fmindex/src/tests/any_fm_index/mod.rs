@@ -0,0 +1,96 @@
+// Assert that AnyFmIndex returns the same results as the FmIndex of each type
+
+use crate::{
+    AlignedBlob, AnyFmIndex, FmIndex, FmIndexBuilder, LoadError, TextEncoder, TypeDescriptor, Position,
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    Block, blocks::{Block2, Block3, Block4, Block5, Block6},
+    text_encoders::{EncodingTable, PassThrough},
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn build_blob<P: Position, B: Block, E: TextEncoder>(
+    text: Vec<u8>,
+    symbol_count: u32,
+    text_encoder: E,
+) -> AlignedBlob {
+    let builder = FmIndexBuilder::<P, B, E>::new(text.len(), symbol_count, text_encoder).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(2)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(3)).unwrap();
+    builder.build_to_aligned_vec(text).unwrap()
+}
+
+fn assert_same_results<P: Position, B: Block, E: TextEncoder>(
+    blob: &[u8],
+    patterns: &[Vec<u8>],
+) {
+    let fm_index = FmIndex::<P, B, E>::load(blob).unwrap();
+    let any_fm_index = AnyFmIndex::load(blob).unwrap();
+    assert_eq!(any_fm_index.type_descriptor(), TypeDescriptor::of::<P, B, E>());
+    assert!(any_fm_index.verify().is_ok());
+
+    patterns.iter().for_each(|pattern| {
+        assert_eq!(any_fm_index.count(pattern), fm_index.count(pattern).as_u64());
+        let expected: Vec<u64> = fm_index.locate(pattern).into_iter().map(|x| x.as_u64()).collect();
+        assert_eq!(any_fm_index.locate(pattern), expected);
+    });
+}
+
+fn assert_all_types<P: Position, B: Block>(
+    chr_list: &[u8],
+    text: &[u8],
+    patterns: &[Vec<u8>],
+) {
+    let symbols = chr_list.chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+    let symbol_count = encoding_table.symbol_count();
+    if B::MAX_SYMBOL < symbol_count {
+        return;
+    }
+
+    let encoded_text: Vec<u8> = text.iter().map(|&c| encoding_table.idx_of(c)).collect();
+    let encoded_patterns: Vec<Vec<u8>> = patterns.iter().map(|pattern| {
+        pattern.iter().map(|&c| encoding_table.idx_of(c)).collect()
+    }).collect();
+
+    let blob = build_blob::<P, B, EncodingTable>(text.to_vec(), symbol_count, encoding_table);
+    assert_same_results::<P, B, EncodingTable>(&blob, patterns);
+    let blob = build_blob::<P, B, PassThrough>(encoded_text, symbol_count, PassThrough);
+    assert_same_results::<P, B, PassThrough>(&blob, &encoded_patterns);
+}
+
+#[test]
+fn any_fm_index_matches_typed_fm_index() {
+    let chr_list = gen_rand_chr_list(3);
+    let text = gen_rand_text(&chr_list, 100, 300);
+    let patterns: Vec<Vec<u8>> = (0..30).map(|_| gen_rand_pattern(&text, 1, 8)).collect();
+
+    macro_rules! for_blocks {
+        ( $p:ty ) => {
+            for_vectors!($p, Block2);
+            for_vectors!($p, Block3);
+            for_vectors!($p, Block4);
+            for_vectors!($p, Block5);
+            for_vectors!($p, Block6);
+        };
+    }
+    macro_rules! for_vectors {
+        ( $p:ty, $b:ident ) => {
+            assert_all_types::<$p, $b<u32>>(&chr_list, &text, &patterns);
+            assert_all_types::<$p, $b<u64>>(&chr_list, &text, &patterns);
+            assert_all_types::<$p, $b<u128>>(&chr_list, &text, &patterns);
+        };
+    }
+    for_blocks!(u32);
+    for_blocks!(u64);
+}
+
+#[test]
+fn unsupported_type_is_rejected() {
+    let text = vec![1, 2, 3, 1, 2, 3, 3];
+    let mut blob = build_blob::<u32, Block2<u64>, PassThrough>(text, 4, PassThrough);
+    assert!(AnyFmIndex::load(&blob).is_ok());
+
+    // Type descriptor starts at 8 with the position bits
+    blob[8] = 16;
+    assert!(matches!(AnyFmIndex::load(&blob), Err(LoadError::UnsupportedType(_))));
+}
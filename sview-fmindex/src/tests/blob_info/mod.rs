@@ -0,0 +1,64 @@
+use crate::{
+    BlobInfo, FmIndexBuilder, LoadError, TypeDescriptor,
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    blocks::{Block2, Block3},
+    text_encoders::{EncodingTable, PassThrough},
+};
+
+#[test]
+fn inspect_reports_headers_and_sections() {
+    let symbols: &[&[u8]] = &[b"Aa", b"Cc", b"Gg", b"Tt"];
+    let encoding_table = EncodingTable::from_symbols(symbols);
+    let symbol_count = encoding_table.symbol_count();
+
+    let text = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZ".to_vec();
+    let text_len = text.len();
+    let builder = FmIndexBuilder::<u64, Block3<u32>, EncodingTable>::new(
+        text_len,
+        symbol_count,
+        encoding_table.clone(),
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(3)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(4)).unwrap();
+    let mut blob = vec![0; builder.blob_size()];
+    builder.build(text, &mut blob).unwrap();
+
+    let blob_info = BlobInfo::inspect(&blob).unwrap();
+    assert_eq!(blob_info.type_descriptor, TypeDescriptor::of::<u64, Block3<u32>, EncodingTable>());
+    assert_eq!(blob_info.text_len, text_len as u64);
+    assert_eq!(blob_info.symbol_count, symbol_count);
+    assert_eq!(blob_info.suffix_array_sampling_ratio, 4);
+    assert_eq!(blob_info.lookup_table_kmer_size, 3);
+    assert_eq!(blob_info.blob_size, blob.len());
+    assert_eq!(blob_info.encoding_table.as_ref().unwrap().mapping(), encoding_table.mapping());
+
+    // Sections are in order and cover the whole blob
+    let mut expected_offset = 0;
+    for section in blob_info.sections.iter() {
+        assert!(section.offset >= expected_offset);
+        assert!(section.offset - expected_offset < 16, "too much padding before {}", section.name);
+        expected_offset = section.offset + section.size;
+    }
+    assert!(blob.len() - expected_offset < 16);
+    assert_eq!(blob_info.section("magic number").unwrap().offset, 0);
+    assert_eq!(blob_info.section("type descriptor").unwrap().offset, 8);
+    assert_eq!(blob_info.section("text encoder").unwrap().size, 256);
+    assert_eq!(blob_info.section("suffix array").unwrap().size, 8 * text_len.div_ceil(4));
+}
+
+#[test]
+fn inspect_does_not_need_types_or_alignment() {
+    let text = vec![1, 2, 3, 1, 2, 3, 3];
+    let builder = FmIndexBuilder::<u32, Block2<u64>, PassThrough>::new(text.len(), 4, PassThrough).unwrap();
+    let mut blob = vec![0; builder.blob_size() + 1];
+    builder.build(text, &mut blob[..builder.blob_size()]).unwrap();
+    blob.copy_within(0..builder.blob_size(), 1);
+
+    let blob_info = BlobInfo::inspect(&blob[1..]).unwrap();
+    assert_eq!(blob_info.type_descriptor.to_string(), "<u32, Block2<u64>, PassThrough>");
+    assert_eq!(blob_info.text_len, 7);
+    assert!(blob_info.encoding_table.is_none());
+
+    assert!(matches!(BlobInfo::inspect(&blob[1..20]), Err(LoadError::TruncatedHeader)));
+    assert!(matches!(BlobInfo::inspect(&[0; 64]), Err(LoadError::InvalidFormat)));
+}
@@ -0,0 +1,228 @@
+// Assert that the queries over the blob sources give the same results as the slice view
+
+use std::io;
+use std::sync::{mpsc, Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::{
+    BlobSource, CachedSource, FileSource, FmIndex, FmIndexBuilder, LoadError, OpenError, Position, SourcedFmIndex,
+    Block, blocks::{Block2, Block3},
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn build_blob<P: Position, B: Block>(chr_list: &[u8], text: &[u8]) -> Vec<u8> {
+    let symbols = chr_list.chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+    let builder = FmIndexBuilder::<P, B, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(3)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(3)).unwrap();
+    builder.build_to_aligned_vec(text.to_vec()).unwrap().to_vec()
+}
+
+fn assert_same_results<S: BlobSource, P: Position + 'static, B: Block + 'static>(
+    sourced_fm_index: &SourcedFmIndex<S, P, B, EncodingTable>,
+    fm_index: &FmIndex<P, B, EncodingTable>,
+    patterns: &[Vec<u8>],
+) {
+    patterns.iter().for_each(|pattern| {
+        assert_eq!(sourced_fm_index.count(pattern).unwrap(), fm_index.count(pattern));
+        let mut expected = fm_index.locate(pattern);
+        expected.sort();
+        let mut locations = sourced_fm_index.locate(pattern).unwrap();
+        locations.sort();
+        assert_eq!(locations, expected);
+        let mut locations = sourced_fm_index.locate_rev_iter(pattern.iter().rev().copied()).unwrap();
+        locations.sort();
+        assert_eq!(locations, expected);
+        // Lazy locations in the same order
+        let locations = sourced_fm_index.locate_iter(pattern).unwrap().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(locations, sourced_fm_index.locate(pattern).unwrap());
+        let locations = sourced_fm_index.locate_iter_from_rev_iter(pattern.iter().rev().copied()).unwrap()
+            .rev().collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(locations, sourced_fm_index.locate(pattern).unwrap().into_iter().rev().collect::<Vec<_>>());
+    });
+}
+
+fn assert_sources_give_same_results<P: Position + 'static, B: Block + 'static>() {
+    let chr_list = gen_rand_chr_list(4);
+    let text = gen_rand_text(&chr_list, 3000, 5000);
+    let patterns: Vec<Vec<u8>> = (0..50).map(|_| gen_rand_pattern(&text, 1, 12)).collect();
+    let blob = build_blob::<P, B>(&chr_list, &text);
+    let aligned_blob = crate::AlignedBlob::copy_from_slice(&blob, B::ALIGN_SIZE);
+    let fm_index = FmIndex::<P, B, EncodingTable>::load(&aligned_blob).unwrap();
+
+    // Slice
+    let sourced_fm_index = SourcedFmIndex::<_, P, B, EncodingTable>::open(&blob[..]).unwrap();
+    assert_same_results(&sourced_fm_index, &fm_index, &patterns);
+
+    // File
+    let path = std::env::temp_dir().join(format!("sview-fmindex-source-{}-{}.blob", std::process::id(), P::BITS));
+    std::fs::write(&path, &blob).unwrap();
+    let file_source = FileSource::open(&path).unwrap();
+    let sourced_fm_index = SourcedFmIndex::<_, P, B, EncodingTable>::open(file_source).unwrap();
+    assert_same_results(&sourced_fm_index, &fm_index, &patterns);
+
+    // Cached file, with pages smaller than the items
+    let cached_source = CachedSource::new(FileSource::open(&path).unwrap(), 7, 4).unwrap();
+    let sourced_fm_index = SourcedFmIndex::<_, P, B, EncodingTable>::open(cached_source).unwrap();
+    assert_same_results(&sourced_fm_index, &fm_index, &patterns);
+    assert!(sourced_fm_index.source().stats().cached_pages <= 4);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn sources_give_same_results_as_slice() {
+    assert_sources_give_same_results::<u32, Block2<u64>>();
+    assert_sources_give_same_results::<u64, Block3<u128>>();
+}
+
+#[test]
+fn cache_keeps_recently_used_pages() {
+    let bytes: Vec<u8> = (0..1000).map(|x| (x % 251) as u8).collect();
+    let cached_source = CachedSource::new(bytes.clone(), 64, 3).unwrap();
+
+    // Reads across the pages
+    for (offset, len) in [(0, 10), (60, 10), (100, 200), (990, 10), (0, 1000)] {
+        let mut buf = vec![0; len];
+        cached_source.read_exact_at(offset as u64, &mut buf).unwrap();
+        assert_eq!(&buf[..], &bytes[offset..offset + len]);
+    }
+    assert_eq!(cached_source.stats().cached_pages, 3);
+    assert_eq!(
+        cached_source.read_exact_at(995, &mut [0; 10]).unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof,
+    );
+
+    // The least recently used page is evicted
+    cached_source.clear();
+    let hits = cached_source.stats().hits;
+    let mut buf = [0; 1];
+    [0, 64, 128, 0, 192].into_iter().for_each(|offset| cached_source.read_exact_at(offset, &mut buf).unwrap());
+    let stats = cached_source.stats();
+    assert_eq!((stats.cached_pages, stats.hits), (3, hits + 1));
+    cached_source.read_exact_at(0, &mut buf).unwrap(); // kept
+    assert_eq!(cached_source.stats().hits, hits + 2);
+    cached_source.read_exact_at(64, &mut buf).unwrap(); // evicted
+    assert_eq!(cached_source.stats().hits, hits + 2);
+}
+
+// Source that fails after it is broken
+// Source of which the reads of the first page wait until it is released
+struct BlockingSource {
+    bytes: Vec<u8>,
+    first_page_reads: AtomicUsize,
+    is_released: Mutex<bool>,
+    released: Condvar,
+}
+
+impl BlobSource for BlockingSource {
+    fn size(&self) -> io::Result<u64> {
+        Ok(self.bytes.len() as u64)
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        if offset == 0 {
+            self.first_page_reads.fetch_add(1, Ordering::SeqCst);
+            let is_released = self.is_released.lock().unwrap();
+            drop(self.released.wait_while(is_released, |is_released| !*is_released).unwrap());
+        }
+        self.bytes.read_exact_at(offset, buf)
+    }
+}
+
+#[test]
+fn cache_is_not_locked_while_pages_are_read() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    let source = BlockingSource {
+        bytes: bytes.clone(),
+        first_page_reads: AtomicUsize::new(0),
+        is_released: Mutex::new(false),
+        released: Condvar::new(),
+    };
+    let cached_source = CachedSource::new(source, 64, 4).unwrap();
+
+    let other_page_read = thread::scope(|scope| {
+        // Readers of the first page, of which the second waits for the first
+        let first_readers = [0, 8].map(|offset| {
+            let cached_source = &cached_source;
+            let reader = scope.spawn(move || {
+                let mut buf = [0; 8];
+                cached_source.read_exact_at(offset, &mut buf).unwrap();
+                buf
+            });
+            while cached_source.inner().first_page_reads.load(Ordering::SeqCst) == 0 {
+                thread::yield_now();
+            }
+            (offset, reader)
+        });
+
+        // The other page is read while the first page is being read
+        let (sender, receiver) = mpsc::channel();
+        let cached_source = &cached_source;
+        scope.spawn(move || {
+            let mut buf = [0; 8];
+            cached_source.read_exact_at(64, &mut buf).unwrap();
+            let _ = sender.send(buf);
+        });
+        let other_page_read = receiver.recv_timeout(Duration::from_secs(10));
+
+        *cached_source.inner().is_released.lock().unwrap() = true;
+        cached_source.inner().released.notify_all();
+        for (offset, reader) in first_readers {
+            let offset = offset as usize;
+            assert_eq!(reader.join().unwrap()[..], bytes[offset..offset + 8]);
+        }
+        other_page_read
+    });
+    assert_eq!(other_page_read.unwrap()[..], bytes[64..72]);
+
+    // The first page is read from the inner source once
+    assert_eq!(cached_source.inner().first_page_reads.load(Ordering::SeqCst), 1);
+    let stats = cached_source.stats();
+    assert_eq!((stats.hits, stats.misses, stats.cached_pages), (1, 2, 2));
+}
+
+struct BreakableSource {
+    blob: Vec<u8>,
+    is_broken: AtomicBool,
+}
+
+impl BlobSource for BreakableSource {
+    fn size(&self) -> io::Result<u64> {
+        self.blob.size()
+    }
+    fn read_exact_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        if self.is_broken.load(Ordering::Relaxed) {
+            return Err(io::Error::other("broken source"));
+        }
+        self.blob.read_exact_at(offset, buf)
+    }
+}
+
+#[test]
+fn source_errors_are_returned() {
+    let chr_list = b"ACGT".to_vec();
+    let text = gen_rand_text(&chr_list, 500, 500);
+    let blob = build_blob::<u32, Block2<u64>>(&chr_list, &text);
+
+    // Failed reads during the queries
+    let source = BreakableSource { blob: blob.clone(), is_broken: AtomicBool::new(false) };
+    let sourced_fm_index = SourcedFmIndex::<_, u32, Block2<u64>, EncodingTable>::open(source).unwrap();
+    sourced_fm_index.source().is_broken.store(true, Ordering::Relaxed);
+    assert!(sourced_fm_index.locate(b"ACGTACGT").is_err());
+
+    // Invalid blobs
+    let result = SourcedFmIndex::<_, u32, Block2<u64>, EncodingTable>::open(&blob[..blob.len() - 8]);
+    assert!(matches!(result, Err(OpenError::Load(LoadError::MismatchedBlobSize(_, _)))));
+    let result = SourcedFmIndex::<_, u32, Block2<u64>, EncodingTable>::open(&blob[..20]);
+    assert!(matches!(result, Err(OpenError::Load(LoadError::TruncatedHeader))));
+    let result = SourcedFmIndex::<_, u64, Block2<u64>, EncodingTable>::open(&blob[..]);
+    assert!(matches!(result, Err(OpenError::Load(LoadError::TypeMismatch { .. }))));
+}
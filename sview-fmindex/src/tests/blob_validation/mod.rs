@@ -0,0 +1,236 @@
+use crate::{
+    AlignedBlob, FmIndex, FmIndexBuilder, LoadError, VerifyError, TypeDescriptor, Position,
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    Block, blocks::{Block2, Block3, Block4, Block5, Block6},
+    text_encoders::{EncodingTable, PassThrough},
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text};
+
+fn build_blob() -> AlignedBlob {
+    let symbols: &[&[u8]] = &[b"Aa", b"Cc", b"Gg", b"Tt"];
+    let encoding_table = EncodingTable::from_symbols(symbols);
+    let symbol_count = encoding_table.symbol_count();
+
+    let text = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZ".to_vec();
+    let builder = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(
+        text.len(),
+        symbol_count,
+        encoding_table,
+    ).unwrap();
+    builder.build_to_aligned_vec(text).unwrap()
+}
+
+#[test]
+fn type_mismatch_is_rejected() {
+    let blob = build_blob();
+    assert!(FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob).is_ok());
+
+    let result = FmIndex::<u64, Block3<u128>, PassThrough>::load(&blob);
+    match result {
+        Err(LoadError::TypeMismatch { expected, found }) => {
+            assert_eq!(expected, TypeDescriptor::of::<u64, Block3<u128>, PassThrough>());
+            assert_eq!(found, TypeDescriptor::of::<u32, Block2<u64>, EncodingTable>());
+            assert_eq!(found.to_string(), "<u32, Block2<u64>, EncodingTable>");
+        },
+        _ => panic!("Loading with mismatched types must fail"),
+    }
+    // Each type differs
+    assert!(matches!(
+        FmIndex::<u64, Block2<u64>, EncodingTable>::load(&blob),
+        Err(LoadError::TypeMismatch { .. }),
+    ));
+    assert!(matches!(
+        FmIndex::<u32, Block3<u64>, EncodingTable>::load(&blob),
+        Err(LoadError::TypeMismatch { .. }),
+    ));
+    assert!(matches!(
+        FmIndex::<u32, Block2<u32>, EncodingTable>::load(&blob),
+        Err(LoadError::TypeMismatch { .. }),
+    ));
+    assert!(matches!(
+        FmIndex::<u32, Block2<u64>, PassThrough>::load(&blob),
+        Err(LoadError::TypeMismatch { .. }),
+    ));
+}
+
+#[test]
+fn truncated_blob_is_rejected() {
+    let blob = build_blob();
+    for len in 0..blob.len() {
+        let result = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob[..len]);
+        assert!(
+            matches!(result, Err(LoadError::TruncatedHeader) | Err(LoadError::MismatchedBlobSize(..))),
+            "Truncated blob of {} bytes must be rejected", len,
+        );
+    }
+}
+
+#[test]
+fn misaligned_blob_is_rejected() {
+    let blob = build_blob();
+    let mut buffer = vec![0_u8; blob.len() + 8];
+    let start = if buffer.as_ptr() as usize % 8 == 0 { 1 } else { 0 };
+    buffer[start..start + blob.len()].copy_from_slice(&blob);
+
+    let result = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&buffer[start..start + blob.len()]);
+    match result {
+        Err(LoadError::MisalignedBlob { required, offset }) => {
+            assert_eq!(required, 8);
+            assert_ne!(offset, 0);
+        },
+        _ => panic!("Misaligned blob must be rejected"),
+    }
+}
+
+// Table of contents with 12 sections
+const TOC_SIZE: usize = 8 + 12 * 24;
+// Checksums of 13 sections, aligned to 8 bytes
+const CHECKSUM_HEADER_SIZE: usize = 56;
+
+#[test]
+fn corrupted_header_does_not_panic() {
+    let blob = build_blob();
+    // Headers (magic number, type descriptor, table of contents, encoding table, count array, suffix array, bwm and checksum)
+    let header_size = 16 + TOC_SIZE + 256 + 24 + 16 + 24 + CHECKSUM_HEADER_SIZE;
+    for idx in 0..header_size {
+        for value in [0, 1, 0x7f, 0xff] {
+            let mut corrupted = blob.clone();
+            corrupted[idx] = value;
+            let _ = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&corrupted);
+        }
+    }
+}
+
+#[test]
+fn verify_names_corrupted_section() {
+    let blob = build_blob();
+    let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob).unwrap();
+    assert!(fm_index.verify().is_ok());
+
+    let assert_corrupted_section = |idx: usize, expected_section: &str| {
+        let mut corrupted = blob.clone();
+        corrupted[idx] ^= 0b1000;
+        let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&corrupted).unwrap();
+        match fm_index.verify() {
+            Err(VerifyError::ChecksumMismatch { section, .. }) => assert_eq!(section, expected_section),
+            _ => panic!("Corruption of the {} must be detected", expected_section),
+        }
+    };
+    // Symbol 'X' in encoding table
+    assert_corrupted_section(16 + TOC_SIZE + b'X' as usize, "text encoder");
+    // Flags in the table of contents
+    assert_corrupted_section(16 + 8 + 4, "prefix");
+    // Last byte of the blob
+    assert_corrupted_section(blob.len() - 1, "blocks");
+}
+
+fn assert_valid_structure<P: Position, B: Block>(chr_list: &[u8], text: Vec<u8>) {
+    if B::MAX_SYMBOL < chr_list.len() as u32 {
+        return;
+    }
+    let symbols = chr_list.chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+    let symbol_count = encoding_table.symbol_count();
+    for (lt_config, sa_config) in [
+        (LookupTableConfig::None, SuffixArrayConfig::Uncompressed),
+        (LookupTableConfig::KmerSize(3), SuffixArrayConfig::Compressed(3)),
+    ] {
+        let builder = FmIndexBuilder::<P, B, EncodingTable>::new(
+            text.len(),
+            symbol_count,
+            encoding_table.clone(),
+        ).unwrap()
+            .set_lookup_table_config(lt_config).unwrap()
+            .set_suffix_array_config(sa_config).unwrap();
+        let blob = builder.build_to_aligned_vec(text.clone()).unwrap();
+        let fm_index = FmIndex::<P, B, EncodingTable>::load(&blob).unwrap();
+        fm_index.validate_structure().unwrap();
+    }
+}
+
+#[test]
+fn built_blobs_have_valid_structure() {
+    for chr_count in [2, 4, 7, 16] {
+        let chr_list = gen_rand_chr_list(chr_count);
+        // Includes the text lengths of multiples of block length
+        for text_len in [chr_count, 127, 128, 256, 300] {
+            let text = gen_rand_text(&chr_list, text_len, text_len);
+            macro_rules! for_vectors {
+                ( $p: ty, $( $b: ident ),* ) => {
+                    $(
+                        assert_valid_structure::<$p, $b<u32>>(&chr_list, text.clone());
+                        assert_valid_structure::<$p, $b<u64>>(&chr_list, text.clone());
+                        assert_valid_structure::<$p, $b<u128>>(&chr_list, text.clone());
+                    )*
+                };
+            }
+            for_vectors!(u32, Block2, Block3, Block4, Block5, Block6);
+            for_vectors!(u64, Block2, Block3, Block4, Block5, Block6);
+        }
+    }
+}
+
+#[test]
+fn empty_text_has_valid_structure() {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    let builder = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(
+        0,
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap();
+    let blob = builder.build_to_aligned_vec(Vec::new()).unwrap();
+    let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob).unwrap();
+    fm_index.validate_structure().unwrap();
+    assert_eq!(fm_index.count(b"A"), 0);
+}
+
+#[test]
+fn validate_structure_detects_broken_invariants() {
+    let blob = build_blob();
+    let body_start = 16 + TOC_SIZE + 256 + 24 + 16 + 24 + CHECKSUM_HEADER_SIZE;
+    // Count array (5 x u32, aligned to 24 bytes), k-mer multiplier (1 x usize) and k-mer count table (5 x u32)
+    let suffix_array_start = body_start + 24 + 8 + 24;
+    let text_len = 31_u32;
+
+    let assert_invalid_section = |corrupted: &[u8], expected_section: &str| {
+        let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(corrupted).unwrap();
+        match fm_index.validate_structure() {
+            Err(VerifyError::InvalidStructure { section, .. }) => assert_eq!(section, expected_section),
+            _ => panic!("Broken invariant of the {} must be detected", expected_section),
+        }
+    };
+
+    // Count array is not monotone
+    let mut corrupted = blob.clone();
+    corrupted[body_start + 4..body_start + 8].copy_from_slice(&100_u32.to_ne_bytes());
+    assert_invalid_section(&corrupted, "count array");
+    // Suffix array sample is out of the text
+    let mut corrupted = blob.clone();
+    corrupted[suffix_array_start..suffix_array_start + 4].copy_from_slice(&text_len.to_ne_bytes());
+    assert_invalid_section(&corrupted, "suffix array");
+    // Block is flipped
+    let mut corrupted = blob.clone();
+    let last_idx = corrupted.len() - 1;
+    corrupted[last_idx] ^= 0b1000_0000;
+    assert_invalid_section(&corrupted, "blocks");
+}
+
+#[test]
+fn kmer_multiplier_is_fixed_width_little_endian() {
+    let blob = build_blob();
+    let blob_info = crate::BlobInfo::inspect(&blob).unwrap();
+    let kmer_size = blob_info.lookup_table_kmer_size as usize;
+    let section = blob_info.section("k-mer multiplier").unwrap();
+    assert_eq!(section.size, 8 * kmer_size);
+
+    // Powers of the symbol count with sentinel, from the highest
+    let symbol_with_sentinel_count = blob_info.symbol_count as u64 + 1;
+    let kmer_multiplier: Vec<u64> = blob[section.offset..section.offset + section.size]
+        .chunks_exact(8)
+        .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect();
+    let expected: Vec<u64> = (0..kmer_size as u32).rev()
+        .map(|pos| symbol_with_sentinel_count.pow(pos))
+        .collect();
+    assert_eq!(kmer_multiplier, expected);
+}
@@ -0,0 +1,71 @@
+// Assert that the blob built from the reader or chunks is identical to the blob built from the text
+
+use crate::{
+    AlignedBlob, BuildError, FmIndexBuilder,
+    blocks::Block3,
+    text_encoders::EncodingTable,
+};
+
+const TEXT: &[u8] = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA";
+
+fn builder(text_len: usize) -> FmIndexBuilder<u32, Block3<u64>, EncodingTable> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    FmIndexBuilder::new(
+        text_len,
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+}
+
+#[test]
+fn blob_is_built_from_reader_and_chunks() {
+    let builder = builder(TEXT.len());
+    let expected = builder.build_to_aligned_vec(TEXT.to_vec()).unwrap();
+
+    let mut blob = AlignedBlob::for_block::<Block3<u64>>(builder.blob_size());
+    builder.build_from_reader(TEXT, &mut blob).unwrap();
+    assert_eq!(&blob[..], &expected[..]);
+
+    // Chunks of records, with the empty ones
+    let records: Vec<&[u8]> = vec![&TEXT[..10], b"", &TEXT[10..11], &TEXT[11..40], &TEXT[40..]];
+    let mut blob = AlignedBlob::for_block::<Block3<u64>>(builder.blob_size());
+    builder.build_from_chunks(&records, &mut blob).unwrap();
+    assert_eq!(&blob[..], &expected[..]);
+
+    // Length counted in a first pass
+    let text_len = records.iter().map(|record| record.len()).sum();
+    let builder = self::builder(text_len);
+    let mut blob = AlignedBlob::for_block::<Block3<u64>>(builder.blob_size());
+    builder.build_from_chunks(records.iter().map(|record| record.to_vec()), &mut blob).unwrap();
+    assert_eq!(&blob[..], &expected[..]);
+}
+
+#[test]
+fn mismatched_text_length_is_rejected() {
+    let builder = builder(TEXT.len());
+    let mut blob = AlignedBlob::for_block::<Block3<u64>>(builder.blob_size());
+    let longer_text = [TEXT, b"ACGT"].concat();
+
+    let result = builder.build_from_reader(&TEXT[1..], &mut blob);
+    assert!(matches!(result, Err(BuildError::UnmatchedTextLength(expected, actual)) if expected == TEXT.len() && actual == TEXT.len() - 1));
+    let result = builder.build_from_reader(&longer_text[..], &mut blob);
+    assert!(matches!(result, Err(BuildError::TextLongerThanLength(expected)) if expected == TEXT.len()));
+    let result = builder.build_from_chunks([&TEXT[..20], &TEXT[21..]], &mut blob);
+    assert!(matches!(result, Err(BuildError::UnmatchedTextLength(_, actual)) if actual == TEXT.len() - 1));
+    let result = builder.build_from_chunks([TEXT, b"ACGT"], &mut blob);
+    assert!(matches!(result, Err(BuildError::TextLongerThanLength(expected)) if expected == TEXT.len()));
+    // The error is returned at the chunk over the length, without taking the rest
+    let result = builder.build_from_chunks(std::iter::repeat(b"ACGT"), &mut blob);
+    assert!(matches!(result, Err(BuildError::TextLongerThanLength(expected)) if expected == TEXT.len()));
+}
+
+#[test]
+fn text_over_position_type_is_rejected() {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    // With the sentinel, the positions are smaller than `u32::MAX`.
+    let max_text_len = u32::MAX as usize - 2;
+    let result = FmIndexBuilder::<u32, Block3<u64>, EncodingTable>::new(max_text_len + 1, encoding_table.symbol_count(), encoding_table.clone());
+    assert!(matches!(result, Err(BuildError::TextLengthOver(max, actual)) if max == max_text_len as u64 && actual == max_text_len + 1));
+    assert!(FmIndexBuilder::<u32, Block3<u64>, EncodingTable>::new(max_text_len, encoding_table.symbol_count(), encoding_table.clone()).is_ok());
+    assert!(FmIndexBuilder::<u64, Block3<u64>, EncodingTable>::new(max_text_len + 1, encoding_table.symbol_count(), encoding_table).is_ok());
+}
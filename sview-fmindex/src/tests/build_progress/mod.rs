@@ -0,0 +1,118 @@
+// Assert that each phase of the build is reported, and that the cancelled build returns the error
+
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    FmIndexBuilder, BuildError, BuildPhase, CancellationToken,
+    blocks::Block2,
+    build_config::SuffixSortConfig,
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text};
+
+type Reports = Arc<Mutex<Vec<(BuildPhase, u64, u64)>>>;
+
+fn builder_of(text: &[u8]) -> FmIndexBuilder<u32, Block2<u64>, EncodingTable> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    FmIndexBuilder::new(text.len(), encoding_table.symbol_count(), encoding_table).unwrap()
+}
+
+fn recording_sink(reports: &Reports) -> impl Fn(BuildPhase, u64, u64) + Send + Sync + 'static {
+    let reports = reports.clone();
+    move |phase, processed, total| reports.lock().unwrap().push((phase, processed, total))
+}
+
+fn assert_every_phase_reported(reports: &[(BuildPhase, u64, u64)]) {
+    let mut phases: Vec<BuildPhase> = reports.iter().map(|(phase, _, _)| *phase).collect();
+    phases.dedup();
+    assert_eq!(phases, vec![
+        BuildPhase::Counting,
+        BuildPhase::SuffixSorting,
+        BuildPhase::Bwt,
+        BuildPhase::SuffixArraySampling,
+        BuildPhase::BlockEncoding,
+    ]);
+    for phase in phases {
+        let phase_reports: Vec<_> = reports.iter().filter(|(p, _, _)| *p == phase).collect();
+        let (_, first_processed, _) = phase_reports.first().unwrap();
+        let (_, last_processed, last_total) = phase_reports.last().unwrap();
+        assert_eq!(*first_processed, 0);
+        assert_eq!(last_processed, last_total);
+        assert!(phase_reports.iter().all(|(_, processed, total)| processed <= total));
+    }
+}
+
+#[test]
+fn every_phase_is_reported_in_order() {
+    let text = gen_rand_text(&gen_rand_chr_list(4), 5000, 5000);
+
+    let reports = Reports::default();
+    builder_of(&text)
+        .set_progress_sink(recording_sink(&reports))
+        .build_to_aligned_vec(text.clone()).unwrap();
+    let reports = reports.lock().unwrap();
+    assert_every_phase_reported(&reports);
+    // The counting and the sort are over the text, with the sentinel for the sort
+    assert!(reports.contains(&(BuildPhase::Counting, 5000, 5000)));
+    assert!(reports.contains(&(BuildPhase::SuffixSorting, 5001, 5001)));
+}
+
+#[test]
+fn every_phase_is_reported_in_external_memory() {
+    let text = gen_rand_text(&gen_rand_chr_list(4), 5000, 5000);
+    let temp_dir = std::env::temp_dir();
+
+    let reports = Reports::default();
+    builder_of(&text)
+        .set_suffix_sort_config(SuffixSortConfig::ExternalMemory {
+            temp_dir,
+            memory_budget: SuffixSortConfig::MIN_MEMORY_BUDGET,
+        }).unwrap()
+        .set_progress_sink(recording_sink(&reports))
+        .build_to_aligned_vec(text.clone()).unwrap();
+    let reports = reports.lock().unwrap();
+    assert_every_phase_reported(&reports);
+    // The sort is not restarted at the rounds of the prefix doubling.
+    let sorted: Vec<u64> = reports.iter()
+        .filter(|(phase, _, _)| *phase == BuildPhase::SuffixSorting)
+        .map(|(_, processed, _)| *processed)
+        .collect();
+    assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert_eq!(*sorted.last().unwrap(), 5001);
+}
+
+#[test]
+fn cancelled_token_stops_the_build() {
+    let text = gen_rand_text(&gen_rand_chr_list(4), 5000, 5000);
+
+    // Cancelled before the build
+    let token = CancellationToken::new();
+    token.cancel();
+    let result = builder_of(&text)
+        .set_cancellation_token(token)
+        .build_to_aligned_vec(text.clone());
+    assert!(matches!(result, Err(BuildError::Cancelled)));
+
+    // Cancelled from the sink, during the suffix sort
+    let token = CancellationToken::new();
+    let reports = Reports::default();
+    let sink = {
+        let token = token.clone();
+        let record = recording_sink(&reports);
+        move |phase, processed, total| {
+            record(phase, processed, total);
+            if phase == BuildPhase::SuffixSorting {
+                token.cancel();
+            }
+        }
+    };
+    let result = builder_of(&text)
+        .set_progress_sink(sink)
+        .set_cancellation_token(token.clone())
+        .build_to_aligned_vec(text.clone());
+    assert!(matches!(result, Err(BuildError::Cancelled)));
+    assert!(token.is_cancelled());
+    assert!(reports.lock().unwrap().iter().all(|(phase, _, _)| {
+        matches!(phase, BuildPhase::Counting | BuildPhase::SuffixSorting)
+    }));
+}
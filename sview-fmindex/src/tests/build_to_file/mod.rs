@@ -0,0 +1,105 @@
+// Assert that the blob built into a file or writer is identical to the blob built in memory
+
+use std::io::{Cursor, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::{
+    AlignedBlob, BuildError, FmIndex, FmIndexBuilder,
+    blocks::Block3,
+    text_encoders::EncodingTable,
+};
+
+const TEXT: &[u8] = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA";
+
+fn builder() -> FmIndexBuilder<u64, Block3<u128>, EncodingTable> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    FmIndexBuilder::new(
+        TEXT.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+}
+
+fn build_in_memory() -> AlignedBlob {
+    let builder = builder();
+    builder.build_to_aligned_vec(TEXT.to_vec()).unwrap()
+}
+
+// Empty directory for the test
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sview-fmindex-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+fn file_names(dir: &PathBuf) -> Vec<String> {
+    std::fs::read_dir(dir).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect()
+}
+
+#[test]
+fn blob_is_built_to_path() {
+    let dir = test_dir("build-to-path");
+    let path = dir.join("index.blob");
+
+    // Replaces the existing file
+    std::fs::write(&path, b"old blob").unwrap();
+    builder().build_to_path(TEXT.to_vec(), &path).unwrap();
+    let blob = std::fs::read(&path).unwrap();
+    assert_eq!(blob, &build_in_memory()[..]);
+    assert_eq!(file_names(&dir), vec!["index.blob".to_string()]);
+
+    // Temporary file is removed on error
+    let result = builder().build_to_path(TEXT[1..].to_vec(), dir.join("failed.blob"));
+    assert!(matches!(result, Err(BuildError::UnmatchedTextLength(..))));
+    assert_eq!(file_names(&dir), vec!["index.blob".to_string()]);
+
+    // Missing directory
+    let result = builder().build_to_path(TEXT.to_vec(), dir.join("missing").join("index.blob"));
+    assert!(matches!(result, Err(BuildError::Io(_))));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn blob_is_built_to_writer() {
+    let mut writer = Cursor::new(b"prefix".to_vec());
+    writer.seek(SeekFrom::End(0)).unwrap();
+    builder().build_to_writer(TEXT.to_vec(), &mut writer).unwrap();
+    let written = writer.into_inner();
+    assert_eq!(&written[..6], b"prefix");
+    assert_eq!(&written[6..], &build_in_memory()[..]);
+
+    let blob = build_in_memory();
+    let fm_index = FmIndex::<u64, Block3<u128>, EncodingTable>::load(&blob).unwrap();
+    assert_eq!(fm_index.locate(b"ACG"), vec![31, 35]);
+}
+
+#[test]
+fn writer_is_rewound_on_error() {
+    // Fails after the first write
+    struct FailingWriter(Cursor<Vec<u8>>);
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0.position() > 4 {
+                return Err(std::io::Error::other("disk full"));
+            }
+            self.0.write(&buf[..buf.len().min(8)])
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Seek for FailingWriter {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.0.seek(pos)
+        }
+    }
+
+    let mut writer = FailingWriter(Cursor::new(Vec::new()));
+    writer.0.set_position(2);
+    let result = builder().build_to_writer(TEXT.to_vec(), &mut writer);
+    assert!(matches!(result, Err(BuildError::Io(_))));
+    assert_eq!(writer.0.position(), 2);
+}
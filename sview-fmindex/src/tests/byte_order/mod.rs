@@ -0,0 +1,101 @@
+use crate::{
+    AlignedBlob, AnyFmIndex, BlobInfo, ByteOrder, FmIndex, FmIndexBuilder, LoadError, convert_endianness,
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    blocks::Block4,
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn build_blob(text: Vec<u8>, chr_list: &[u8]) -> AlignedBlob {
+    let symbols = chr_list.chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+    let builder = FmIndexBuilder::<u64, Block4<u128>, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(3)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(2)).unwrap();
+    builder.build_to_aligned_vec(text).unwrap()
+}
+
+#[test]
+fn blob_with_other_byte_order_is_rejected() {
+    let chr_list = gen_rand_chr_list(6);
+    let text = gen_rand_text(&chr_list, 100, 300);
+    let blob = build_blob(text, &chr_list);
+
+    let mut converted_blob = vec![0; blob.len()];
+    let new_byte_order = convert_endianness(&blob, &mut converted_blob).unwrap();
+    assert_eq!(new_byte_order, ByteOrder::NATIVE.opposite());
+
+    let expected_error = |result: Result<_, LoadError>| matches!(
+        result,
+        Err(LoadError::ByteOrderMismatch { expected, found })
+            if expected == ByteOrder::NATIVE && found == ByteOrder::NATIVE.opposite()
+    );
+    assert!(expected_error(FmIndex::<u64, Block4<u128>, EncodingTable>::load(&converted_blob).map(|_| ())));
+    assert!(expected_error(AnyFmIndex::load(&converted_blob).map(|_| ())));
+    assert!(expected_error(BlobInfo::inspect(&converted_blob).map(|_| ())));
+}
+
+#[test]
+fn converted_blob_has_reversed_integers() {
+    let chr_list = gen_rand_chr_list(6);
+    let text = gen_rand_text(&chr_list, 100, 300);
+    let blob = build_blob(text, &chr_list);
+    let blob_info = BlobInfo::inspect(&blob).unwrap();
+
+    let mut converted_blob = vec![0; blob.len()];
+    convert_endianness(&blob, &mut converted_blob).unwrap();
+
+    // Every u64 of the suffix array is reversed
+    let section = blob_info.section("suffix array").unwrap();
+    let range = section.offset..section.offset + section.size;
+    blob[range.clone()].chunks_exact(8).zip(converted_blob[range].chunks_exact(8)).for_each(|(original, converted)| {
+        let mut reversed = original.to_vec();
+        reversed.reverse();
+        assert_eq!(converted, &reversed[..]);
+    });
+    // Every u128 vector of the blocks is reversed
+    let section = blob_info.section("blocks").unwrap();
+    let range = section.offset..section.offset + section.size;
+    blob[range.clone()].chunks_exact(16).zip(converted_blob[range].chunks_exact(16)).for_each(|(original, converted)| {
+        let mut reversed = original.to_vec();
+        reversed.reverse();
+        assert_eq!(converted, &reversed[..]);
+    });
+}
+
+#[test]
+fn converting_twice_restores_blob() {
+    let chr_list = gen_rand_chr_list(6);
+    let text = gen_rand_text(&chr_list, 100, 300);
+    let patterns: Vec<Vec<u8>> = (0..30).map(|_| gen_rand_pattern(&text, 1, 8)).collect();
+    let blob = build_blob(text, &chr_list);
+
+    let mut converted_blob = vec![0; blob.len()];
+    convert_endianness(&blob, &mut converted_blob).unwrap();
+    assert_ne!(&blob[..], &converted_blob[..]);
+
+    // Convert misaligned blob back
+    let mut misaligned_blob = vec![0; blob.len() + 1];
+    misaligned_blob[1..].copy_from_slice(&converted_blob);
+    let mut restored_blob = AlignedBlob::for_block::<Block4<u128>>(blob.len());
+    let new_byte_order = convert_endianness(&misaligned_blob[1..], &mut restored_blob).unwrap();
+    assert_eq!(new_byte_order, ByteOrder::NATIVE);
+    assert_eq!(&blob[..], &restored_blob[..]);
+
+    let fm_index = FmIndex::<u64, Block4<u128>, EncodingTable>::load(&restored_blob).unwrap();
+    assert!(fm_index.verify().is_ok());
+    patterns.iter().for_each(|pattern| {
+        assert!(fm_index.count(pattern) > 0);
+    });
+
+    // Size of new blob must match
+    let mut small_blob = vec![0; blob.len() - 1];
+    assert!(matches!(
+        convert_endianness(&blob, &mut small_blob),
+        Err(LoadError::MismatchedBlobSize(_, _)),
+    ));
+}
@@ -0,0 +1,60 @@
+// Assert that the blob sorted in external memory is identical to the blob sorted in memory
+
+use crate::{
+    BuildError, FmIndexBuilder,
+    blocks::Block3,
+    build_config::{LookupTableConfig, SuffixArrayConfig, SuffixSortConfig},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text};
+
+fn builder(text_len: usize, encoding_table: &EncodingTable) -> FmIndexBuilder<u32, Block3<u64>, EncodingTable> {
+    FmIndexBuilder::new(
+        text_len,
+        encoding_table.symbol_count(),
+        encoding_table.clone(),
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(4)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(4)).unwrap()
+}
+
+#[test]
+fn blob_is_same_with_in_memory_sort() {
+    let temp_dir = std::env::temp_dir().join(format!("sview-fmindex-external-sort-{}", std::process::id()));
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let chr_list = gen_rand_chr_list(5);
+    let symbols = chr_list[..4].chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+
+    // Runs over the fan-in of the smallest budget are merged twice.
+    let mut text = gen_rand_text(&chr_list, 200_000, 200_000);
+    text.extend_from_within(..50_000);
+    let expected = builder(text.len(), &encoding_table).build_to_aligned_vec(text.clone()).unwrap();
+    let blob = builder(text.len(), &encoding_table)
+        .set_suffix_sort_config(SuffixSortConfig::ExternalMemory {
+            temp_dir: temp_dir.clone(),
+            memory_budget: SuffixSortConfig::MIN_MEMORY_BUDGET,
+        }).unwrap()
+        .build_to_aligned_vec(text).unwrap();
+    assert!(blob[..] == expected[..]);
+
+    assert_eq!(std::fs::read_dir(&temp_dir).unwrap().count(), 0);
+    std::fs::remove_dir(&temp_dir).unwrap();
+}
+
+#[test]
+fn invalid_external_memory_is_rejected() {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+
+    let result = builder(10, &encoding_table).set_suffix_sort_config(SuffixSortConfig::ExternalMemory {
+        temp_dir: std::env::temp_dir(),
+        memory_budget: 1024,
+    });
+    assert!(matches!(result, Err(BuildError::InvalidConfig(_))));
+
+    let result = builder(10, &encoding_table).set_suffix_sort_config(SuffixSortConfig::ExternalMemory {
+        temp_dir: std::env::temp_dir().join("sview-fmindex-not-existing-dir"),
+        memory_budget: SuffixSortConfig::MIN_MEMORY_BUDGET,
+    }).unwrap().build_to_aligned_vec(b"ACGTACGTAC".to_vec());
+    assert!(matches!(result, Err(BuildError::Io(_))));
+}
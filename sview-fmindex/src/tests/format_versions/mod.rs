@@ -0,0 +1,141 @@
+// Assert that the blobs of the format version 0.0 are loaded and upgraded
+//  - 0.0: magic number aligned by the block, without type descriptor, checksums and table of contents
+//  - 0.1: current version
+
+use crate::{
+    AlignedBlob, AnyFmIndex, BlobInfo, FmIndex, FmIndexBuilder, LoadError, VerifyError, Position,
+    upgrade_blob, upgraded_blob_size,
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    Block, TypeDescriptor, blocks::{Block2, Block3},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn build_blob<P: Position, B: Block>(text: Vec<u8>, chr_list: &[u8]) -> AlignedBlob {
+    let symbols = chr_list.chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+    let builder = FmIndexBuilder::<P, B, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(3)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(2)).unwrap();
+    builder.build_to_aligned_vec(text).unwrap()
+}
+
+// Rewrite the blob of the current version in the layout of the version 0.0
+fn downgrade_blob<B: Block>(blob: &[u8]) -> AlignedBlob {
+    let blob_info = BlobInfo::inspect(blob).unwrap();
+    let checksum_header = blob_info.section("checksum header").unwrap();
+    let toc = blob_info.section("table of contents").unwrap();
+    // No checksum header
+    let checksum_header_size = checksum_header.size.div_ceil(B::ALIGN_SIZE) * B::ALIGN_SIZE;
+    let mut removed_ranges = Vec::new();
+    removed_ranges.push(checksum_header.offset..checksum_header.offset + checksum_header_size);
+    // No table of contents, and the headers follow the type descriptor
+    let toc_size = toc.size.div_ceil(B::ALIGN_SIZE) * B::ALIGN_SIZE;
+    removed_ranges.push(toc.offset..toc.offset + toc_size);
+    // No type descriptor, and the magic number is aligned by the block
+    if B::ALIGN_SIZE <= 8 {
+        removed_ranges.push(8..16);
+    }
+    removed_ranges.sort_by_key(|range| range.start);
+
+    let removed_size: usize = removed_ranges.iter().map(|range| range.len()).sum();
+    let mut old_blob = AlignedBlob::for_block::<B>(blob.len() - removed_size);
+    let mut copied_size = 0;
+    let mut start = 0;
+    for removed_range in removed_ranges.into_iter().chain(std::iter::once(blob.len()..blob.len())) {
+        let kept_bytes = &blob[start..removed_range.start];
+        old_blob[copied_size..copied_size + kept_bytes.len()].copy_from_slice(kept_bytes);
+        copied_size += kept_bytes.len();
+        start = removed_range.end;
+    }
+    // Version 0.0, without the byte order marker
+    old_blob[3] = b'0';
+    old_blob[4] = 0;
+    if B::ALIGN_SIZE > 8 {
+        old_blob[8..16].fill(0);
+    }
+    old_blob
+}
+
+fn assert_untyped_version<P: Position, B: Block>() {
+    let chr_list = gen_rand_chr_list(4);
+    let text = gen_rand_text(&chr_list, 100, 300);
+    let patterns: Vec<Vec<u8>> = (0..30).map(|_| gen_rand_pattern(&text, 1, 8)).collect();
+    let blob = build_blob::<P, B>(text, &chr_list);
+    let fm_index = FmIndex::<P, B, EncodingTable>::load(&blob).unwrap();
+    let old_blob = downgrade_blob::<B>(&blob);
+
+    // Load
+    let old_fm_index = FmIndex::<P, B, EncodingTable>::load(&old_blob).unwrap();
+    patterns.iter().for_each(|pattern| {
+        assert_eq!(old_fm_index.locate(pattern), fm_index.locate(pattern));
+    });
+    assert!(matches!(old_fm_index.verify(), Err(VerifyError::MissingChecksums)));
+    assert!(old_fm_index.validate_structure().is_ok());
+    assert!(matches!(AnyFmIndex::load(&old_blob), Err(LoadError::UntypedBlob)));
+
+    // Upgrade
+    let mut new_blob = AlignedBlob::for_block::<B>(FmIndex::<P, B, EncodingTable>::upgraded_blob_size(&old_blob).unwrap());
+    FmIndex::<P, B, EncodingTable>::upgrade_blob(&old_blob, &mut new_blob).unwrap();
+    assert_eq!(&new_blob[..], &blob[..]);
+
+    // Upgrade with the types given at runtime
+    let type_descriptor = TypeDescriptor::of::<P, B, EncodingTable>();
+    let mut untyped_new_blob = AlignedBlob::for_block::<B>(upgraded_blob_size(&old_blob, &type_descriptor).unwrap());
+    upgrade_blob(&old_blob, &mut untyped_new_blob, &type_descriptor).unwrap();
+    assert_eq!(&untyped_new_blob[..], &blob[..]);
+    // The types recorded in the blob must be the same
+    let other_type_descriptor = TypeDescriptor::of::<P, Block3<u32>, EncodingTable>();
+    assert!(matches!(upgraded_blob_size(&blob, &other_type_descriptor), Err(LoadError::TypeMismatch { .. })));
+}
+
+#[test]
+fn untyped_version_is_loaded_and_upgraded() {
+    assert_untyped_version::<u32, Block2<u64>>();
+    assert_untyped_version::<u64, Block3<u128>>();
+}
+
+#[test]
+fn future_version_is_rejected() {
+    let chr_list = gen_rand_chr_list(4);
+    let text = gen_rand_text(&chr_list, 100, 300);
+    let mut blob = build_blob::<u32, Block2<u64>>(text, &chr_list);
+    blob[3] += 1;
+    assert!(matches!(
+        FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob),
+        Err(LoadError::InvalidFormat),
+    ));
+}
+
+// Blobs built by the release of format version 0.0
+#[test]
+fn released_blobs_are_upgraded() {
+    fn assert_upgraded<P: Position, B: Block>(old_blob: &[u8]) {
+        // Built with the default configs
+        let text = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA".to_vec();
+        let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+        let builder = FmIndexBuilder::<P, B, EncodingTable>::new(
+            text.len(),
+            encoding_table.symbol_count(),
+            encoding_table,
+        ).unwrap();
+        let blob = builder.build_to_aligned_vec(text).unwrap();
+
+        let old_blob = AlignedBlob::copy_from_slice(old_blob, B::ALIGN_SIZE);
+        let old_fm_index = FmIndex::<P, B, EncodingTable>::load(&old_blob).unwrap();
+        assert_eq!(
+            old_fm_index.locate(b"ACG").into_iter().map(|x| x.as_u64()).collect::<Vec<_>>(),
+            vec![31, 35],
+        );
+
+        let mut new_blob = AlignedBlob::for_block::<B>(FmIndex::<P, B, EncodingTable>::upgraded_blob_size(&old_blob).unwrap());
+        FmIndex::<P, B, EncodingTable>::upgrade_blob(&old_blob, &mut new_blob).unwrap();
+        assert_eq!(&new_blob[..], &blob[..]);
+    }
+    assert_upgraded::<u32, Block2<u64>>(include_bytes!("v0_0-u32-block2_u64.blob"));
+    assert_upgraded::<u64, Block3<u128>>(include_bytes!("v0_0-u64-block3_u128.blob"));
+}
@@ -0,0 +1,72 @@
+// Assert that the embedded blob is aligned and loaded
+
+use crate::{
+    FmIndex, FmIndexBuilder, OwnedFmIndex,
+    blocks::Block3,
+    components::{ByteOrder, MagicNumber},
+    text_encoders::EncodingTable,
+};
+
+type P = u32;
+type B = Block3<u128>;
+type E = EncodingTable;
+
+const TEXT: &[u8] = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA";
+
+// Built from `TEXT` by `write_fixture` (little-endian)
+#[cfg(target_endian = "little")]
+static EMBEDDED: &[u8] = crate::include_blob!("fixture.blob");
+
+fn build_blob() -> Vec<u8> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    let builder = FmIndexBuilder::<P, B, E>::new(
+        TEXT.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap();
+    builder.build_to_aligned_vec(TEXT.to_vec()).unwrap().to_vec()
+}
+
+#[test]
+#[ignore = "regenerates the fixture"]
+fn write_fixture() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/tests/include_blob/fixture.blob");
+    std::fs::write(path, build_blob()).unwrap();
+}
+
+#[test]
+#[cfg(target_endian = "little")]
+fn embedded_blob_is_loaded() {
+    assert_eq!(EMBEDDED.as_ptr() as usize % 16, 0);
+
+    let fm_index = FmIndex::<P, B, E>::load(EMBEDDED).unwrap();
+    let mut locations = fm_index.locate(b"ACG");
+    locations.sort();
+    assert_eq!(locations, vec![31, 35]);
+    fm_index.verify().unwrap();
+
+    let owned_fm_index = OwnedFmIndex::<_, P, B, E>::load(EMBEDDED).unwrap();
+    assert_eq!(owned_fm_index.index().count(b"ACG"), 2);
+}
+
+#[test]
+fn prefix_is_checked() {
+    let mut blob = build_blob();
+    assert_eq!(MagicNumber::check_prefix(&blob), Ok(()));
+
+    assert!(MagicNumber::check_prefix(&blob[..4]).is_err());
+    // Other byte order
+    let other_marker = match ByteOrder::NATIVE {
+        ByteOrder::LittleEndian => b'B',
+        ByteOrder::BigEndian => b'L',
+    };
+    blob[4] = other_marker;
+    assert!(MagicNumber::check_prefix(&blob).is_err());
+    // Unsupported version
+    blob[4] = build_blob()[4];
+    blob[3] = b'9';
+    assert!(MagicNumber::check_prefix(&blob).is_err());
+    // Not a blob
+    blob[0] = b'X';
+    assert!(MagicNumber::check_prefix(&blob).is_err());
+}
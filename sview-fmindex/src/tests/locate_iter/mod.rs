@@ -0,0 +1,77 @@
+// Assert that the lazy locations are the same as the collected ones, from both ends
+
+use crate::{
+    AnyFmIndex, FmIndex, FmIndexBuilder, Position,
+    Block, blocks::{Block2, Block3},
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn assert_lazy_locations_are_same<P: Position, B: Block>(sampling_ratio: u32) {
+    let chr_list = gen_rand_chr_list(4);
+    let text = gen_rand_text(&chr_list, 1000, 3000);
+    let symbols = chr_list[..3].chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+    let suffix_array_config = if sampling_ratio == 1 {
+        SuffixArrayConfig::Uncompressed
+    } else {
+        SuffixArrayConfig::Compressed(sampling_ratio)
+    };
+    let blob = FmIndexBuilder::<P, B, EncodingTable>::new(text.len(), encoding_table.symbol_count(), encoding_table).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(3)).unwrap()
+        .set_suffix_array_config(suffix_array_config).unwrap()
+        .build_to_aligned_vec(text.clone()).unwrap();
+    let fm_index = FmIndex::<P, B, EncodingTable>::load(&blob).unwrap();
+    let any_fm_index = AnyFmIndex::load(&blob).unwrap();
+
+    for _ in 0..50 {
+        let pattern = gen_rand_pattern(&text, 1, 6);
+        let expected = fm_index.locate(&pattern);
+
+        let locate_iter = fm_index.locate_iter(&pattern);
+        assert_eq!(locate_iter.len(), expected.len());
+        assert_eq!(locate_iter.collect::<Vec<_>>(), expected);
+        assert_eq!(fm_index.locate_iter(&pattern).rev().collect::<Vec<_>>(), expected.iter().rev().copied().collect::<Vec<_>>());
+        assert_eq!(fm_index.locate_iter_from_rev_iter(pattern.iter().rev().copied()).collect::<Vec<_>>(), expected);
+        // Taken from the front and the back, until they meet
+        let mut locate_iter = fm_index.locate_iter(&pattern);
+        let (mut front, mut back) = (Vec::new(), Vec::new());
+        while let Some(location) = locate_iter.next() {
+            front.push(location);
+            back.extend(locate_iter.next_back());
+        }
+        front.extend(back.into_iter().rev());
+        assert_eq!(front, expected);
+        // Only the first few
+        assert_eq!(fm_index.locate_iter(&pattern).take(3).collect::<Vec<_>>(), expected.iter().take(3).copied().collect::<Vec<_>>());
+
+        assert_eq!(
+            any_fm_index.locate_iter(&pattern).collect::<Vec<_>>(),
+            expected.iter().map(|location| location.as_u64()).collect::<Vec<_>>(),
+        );
+    }
+}
+
+#[test]
+fn lazy_locations_are_same_with_locate() {
+    for sampling_ratio in [1, 2, 5] {
+        assert_lazy_locations_are_same::<u32, Block2<u64>>(sampling_ratio);
+        assert_lazy_locations_are_same::<u64, Block3<u32>>(sampling_ratio);
+    }
+}
+
+#[test]
+fn lazy_locations_of_absent_pattern_are_empty() {
+    let text = b"ACGTACGTACGT".to_vec();
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    let blob = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(text.len(), encoding_table.symbol_count(), encoding_table).unwrap()
+        .build_to_aligned_vec(text).unwrap();
+    let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob).unwrap();
+
+    let mut locate_iter = fm_index.locate_iter(b"AA");
+    assert_eq!(locate_iter.len(), 0);
+    assert_eq!(locate_iter.next(), None);
+    assert_eq!(locate_iter.next_back(), None);
+    assert_eq!(fm_index.locate_iter(b"GTA").collect::<Vec<_>>(), fm_index.locate(b"GTA"));
+}
@@ -0,0 +1,81 @@
+// Assert that the memory-mapped index gives the same results with every option
+
+use std::path::PathBuf;
+
+use crate::{
+    FmIndex, FmIndexBuilder, LoadError, MappedFmIndex, MmapOptions, AccessPattern, HotSections, OpenError,
+    build_config::LookupTableConfig,
+    blocks::{Block2, Block3},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sview-fmindex-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn mapped_index_gives_same_results() {
+    let dir = test_dir("mapped-fm-index");
+    let path = dir.join("index.blob");
+
+    let chr_list = gen_rand_chr_list(4);
+    let text = gen_rand_text(&chr_list, 10_000, 20_000);
+    let patterns: Vec<Vec<u8>> = (0..30).map(|_| gen_rand_pattern(&text, 1, 10)).collect();
+    let encoding_table = EncodingTable::from_symbols(&chr_list.chunks(1).collect::<Vec<_>>());
+    let builder = FmIndexBuilder::<u32, Block3<u64>, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(4)).unwrap();
+    builder.build_to_path(text.clone(), &path).unwrap();
+
+    let blob = std::fs::read(&path).unwrap();
+    let fm_index = FmIndex::<u32, Block3<u64>, EncodingTable>::load(&blob).unwrap();
+
+    let mut options_list = vec![
+        MmapOptions::default(),
+        MmapOptions::lazy(),
+        MmapOptions::low_latency(),
+        MmapOptions::batch(),
+    ];
+    for access_pattern in [AccessPattern::Normal, AccessPattern::Random, AccessPattern::Sequential, AccessPattern::WillNeed] {
+        for hot_sections in [HotSections::Lazy, HotSections::Prefetch, HotSections::Warmup, HotSections::Lock] {
+            options_list.push(MmapOptions { access_pattern, hot_sections, dont_dump: true });
+        }
+    }
+    for options in options_list {
+        let mapped_fm_index = match MappedFmIndex::<u32, Block3<u64>, EncodingTable>::open(&path, options) {
+            Ok(mapped_fm_index) => mapped_fm_index,
+            // Locking can be denied by the limit of the environment
+            Err(OpenError::Io(_)) if options.hot_sections == HotSections::Lock => continue,
+            Err(err) => panic!("Opening with {:?} failed: {}", options, err),
+        };
+        mapped_fm_index.warmup_all();
+        patterns.iter().for_each(|pattern| {
+            assert_eq!(mapped_fm_index.index().locate(pattern), fm_index.locate(pattern));
+        });
+        assert!(mapped_fm_index.index().verify().is_ok());
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn invalid_file_is_rejected() {
+    let dir = test_dir("mapped-fm-index-invalid");
+
+    let result = MappedFmIndex::<u32, Block2<u64>, EncodingTable>::open(dir.join("missing.blob"), MmapOptions::default());
+    assert!(matches!(result, Err(OpenError::Io(_))));
+
+    let path = dir.join("invalid.blob");
+    std::fs::write(&path, b"not an fm-index blob").unwrap();
+    let result = MappedFmIndex::<u32, Block2<u64>, EncodingTable>::open(&path, MmapOptions::default());
+    assert!(matches!(result, Err(OpenError::Load(LoadError::InvalidFormat))));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
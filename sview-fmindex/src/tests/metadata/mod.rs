@@ -0,0 +1,83 @@
+// Assert that the metadata is embedded in the blob and read back without copying
+
+use crate::{
+    AlignedBlob, AnyFmIndex, BlobInfo, FmIndex, FmIndexBuilder, VerifyError, convert_endianness, upgrade_blob, upgraded_blob_size,
+    blocks::Block3,
+    text_encoders::EncodingTable,
+};
+
+const METADATA: &[u8] = b"{\"source\":\"chr1.fa\",\"assembly\":\"GRCh38.p14\",\"symbols\":\"ACGT\"}";
+const TEXT: &[u8] = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA";
+
+fn builder() -> FmIndexBuilder<u32, Block3<u64>, EncodingTable> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    FmIndexBuilder::new(
+        TEXT.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+}
+
+fn build(builder: FmIndexBuilder<u32, Block3<u64>, EncodingTable>) -> AlignedBlob {
+    builder.build_to_aligned_vec(TEXT.to_vec()).unwrap()
+}
+
+#[test]
+fn metadata_is_read_from_blob() {
+    let plain_blob = build(builder());
+    let blob = build(builder().set_metadata(METADATA));
+    // An entry in the table of contents, and the metadata aligned by the block
+    assert_eq!(blob.len(), plain_blob.len() + 24 + METADATA.len().div_ceil(8) * 8);
+
+    let fm_index = FmIndex::<u32, Block3<u64>, EncodingTable>::load(&blob).unwrap();
+    let metadata = fm_index.metadata().unwrap();
+    assert_eq!(metadata, METADATA);
+    // Borrowed from the blob
+    assert!(blob.as_ptr_range().contains(&metadata.as_ptr()));
+    assert!(fm_index.verify().is_ok());
+    assert_eq!(fm_index.locate(b"ACG"), vec![31, 35]);
+    assert_eq!(AnyFmIndex::load(&blob).unwrap().metadata(), Some(METADATA));
+
+    let section = BlobInfo::inspect(&blob).unwrap().section("metadata").unwrap().clone();
+    assert_eq!(&blob[section.offset..section.offset + section.size], METADATA);
+
+    // Without metadata
+    let plain_fm_index = FmIndex::<u32, Block3<u64>, EncodingTable>::load(&plain_blob).unwrap();
+    assert_eq!(plain_fm_index.metadata(), None);
+    assert!(BlobInfo::inspect(&plain_blob).unwrap().section("metadata").is_none());
+    // Empty metadata
+    let blob = build(builder().set_metadata(Vec::new()));
+    assert_eq!(FmIndex::<u32, Block3<u64>, EncodingTable>::load(&blob).unwrap().metadata(), Some(&[][..]));
+}
+
+#[test]
+fn metadata_is_covered_by_checksums() {
+    let mut blob = build(builder().set_metadata(METADATA));
+    let section = BlobInfo::inspect(&blob).unwrap().section("metadata").unwrap().clone();
+    blob[section.offset] ^= 1;
+    let fm_index = FmIndex::<u32, Block3<u64>, EncodingTable>::load(&blob).unwrap();
+    assert!(matches!(
+        fm_index.verify(),
+        Err(VerifyError::ChecksumMismatch { section: "metadata", .. }),
+    ));
+}
+
+#[test]
+fn metadata_is_kept_by_rewriting() {
+    let blob = build(builder().set_metadata(METADATA));
+
+    // Upgrade
+    let type_descriptor = BlobInfo::inspect(&blob).unwrap().type_descriptor;
+    let mut upgraded_blob = AlignedBlob::for_block::<Block3<u64>>(upgraded_blob_size(&blob, &type_descriptor).unwrap());
+    upgrade_blob(&blob, &mut upgraded_blob, &type_descriptor).unwrap();
+    assert_eq!(&upgraded_blob[..], &blob[..]);
+
+    // Convert byte order
+    let section = BlobInfo::inspect(&blob).unwrap().section("metadata").unwrap().clone();
+    let mut converted_blob = vec![0; blob.len()];
+    convert_endianness(&blob, &mut converted_blob).unwrap();
+    assert_eq!(&converted_blob[section.offset..section.offset + section.size], METADATA);
+    let mut restored_blob = vec![0; blob.len()];
+    convert_endianness(&converted_blob, &mut restored_blob).unwrap();
+    assert_eq!(restored_blob, &blob[..]);
+}
@@ -6,5 +6,25 @@ pub mod result_answer;
 mod get_accurate_result;
 mod config_invariance;
 mod text_encoders_consistency;
+mod blob_validation;
+mod blob_info;
+mod any_fm_index;
+mod byte_order;
+mod format_versions;
+mod toc;
+mod metadata;
+mod build_to_file;
+mod build_from_stream;
+mod external_memory;
+mod build_progress;
+mod tuned_build;
+mod locate_iter;
+#[cfg(feature = "parallel")]
+mod parallel_build;
+mod mapped_fm_index;
+mod owned_fm_index;
+mod aligned_blob;
+mod include_blob;
+mod blob_source;
 
 mod readme;
@@ -0,0 +1,80 @@
+// Assert that the owned index is loaded from every storage, and shared across threads
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{
+    AlignedBlob, FmIndexBuilder, MappedFmIndex, MmapOptions, OwnedFmIndex, StableBlob,
+    blocks::Block2,
+    text_encoders::EncodingTable,
+};
+
+type Index<S> = OwnedFmIndex<S, u32, Block2<u64>, EncodingTable>;
+
+fn assert_send_sync_static<T: Send + Sync + 'static>() {}
+
+fn build_blob(text: &[u8]) -> AlignedBlob {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    let builder = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap();
+    builder.build_to_aligned_vec(text.to_vec()).unwrap()
+}
+
+fn assert_locations<S: StableBlob>(index: &Index<S>) {
+    let mut locations = index.index().locate(b"ACG");
+    locations.sort();
+    assert_eq!(locations, vec![31, 35]);
+}
+
+const TEXT: &[u8] = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA";
+
+#[test]
+fn owned_index_is_loaded_from_every_storage() {
+    assert_send_sync_static::<Index<AlignedBlob>>();
+    assert_send_sync_static::<Index<Vec<u8>>>();
+    assert_send_sync_static::<Index<Box<[u8]>>>();
+    assert_send_sync_static::<Index<Arc<[u8]>>>();
+    assert_send_sync_static::<Index<memmap2::Mmap>>();
+
+    let aligned_blob = build_blob(TEXT);
+    assert_locations(&Index::load(aligned_blob.clone()).unwrap());
+    let blob = aligned_blob.to_vec();
+    assert_locations(&Index::load(blob.clone()).unwrap());
+    assert_locations(&Index::load(blob.clone().into_boxed_slice()).unwrap());
+    assert_locations(&Index::load(Arc::<[u8]>::from(blob.clone())).unwrap());
+    let leaked: &'static [u8] = Box::leak(blob.clone().into_boxed_slice());
+    assert_locations(&Index::load(leaked).unwrap());
+
+    // Memory map
+    let path = std::env::temp_dir().join(format!("sview-fmindex-owned-{}.blob", std::process::id()));
+    std::fs::write(&path, &blob).unwrap();
+    let mapped_fm_index = MappedFmIndex::<u32, Block2<u64>, EncodingTable>::open(&path, MmapOptions::default()).unwrap();
+    assert_locations(&mapped_fm_index.into_owned());
+    std::fs::remove_file(&path).unwrap();
+
+    // Storage is returned
+    let index = Index::load(blob.clone()).unwrap();
+    assert_eq!(index.into_storage(), blob);
+}
+
+#[test]
+fn owned_indexes_are_shared_across_threads() {
+    // Moved into the map after loading
+    let mut indexes: HashMap<String, Index<Arc<[u8]>>> = HashMap::new();
+    for name in ["first", "second"] {
+        let index = Index::load(Arc::from(&build_blob(TEXT)[..])).unwrap();
+        indexes.insert(name.to_string(), index);
+    }
+    let indexes = Arc::new(indexes);
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let indexes = Arc::clone(&indexes);
+        std::thread::spawn(move || {
+            indexes.values().for_each(assert_locations);
+        })
+    }).collect();
+    handles.into_iter().for_each(|handle| handle.join().unwrap());
+}
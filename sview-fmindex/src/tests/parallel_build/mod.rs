@@ -0,0 +1,64 @@
+// Assert that the blob built on any number of threads is identical, and gives the accurate results
+
+use crate::{
+    AlignedBlob, FmIndex, FmIndexBuilder, Position, TextEncoder,
+    Block, blocks::{Block2, Block3},
+    build_config::{LookupTableConfig, SuffixArrayConfig},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+fn build_on_threads<P: Position, B: Block>(
+    text: &[u8],
+    encoding_table: &EncodingTable,
+    kmer_size: u32,
+    thread_count: usize,
+) -> AlignedBlob {
+    FmIndexBuilder::<P, B, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table.clone(),
+    ).unwrap()
+        .set_lookup_table_config(LookupTableConfig::KmerSize(kmer_size)).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(3)).unwrap()
+        .set_thread_count(thread_count)
+        .build_to_aligned_vec(text.to_vec()).unwrap()
+}
+
+fn assert_same_blob_on_threads<P: Position, B: Block>(text_len: usize) {
+    let chr_list = gen_rand_chr_list(4);
+    let text = gen_rand_text(&chr_list, text_len, text_len);
+    let symbols = chr_list[..3].chunks(1).collect::<Vec<_>>();
+    let encoding_table = EncodingTable::from_symbols(&symbols);
+
+    // Small k-mer table counted on each thread, and large one counted at once
+    for kmer_size in [2, 8] {
+        let blob = build_on_threads::<P, B>(&text, &encoding_table, kmer_size, 1);
+        for thread_count in [2, 7] {
+            let other_blob = build_on_threads::<P, B>(&text, &encoding_table, kmer_size, thread_count);
+            assert_eq!(&blob[..], &other_blob[..]);
+        }
+
+        let fm_index = FmIndex::<P, B, EncodingTable>::load(&blob).unwrap();
+        for _ in 0..20 {
+            let pattern = gen_rand_pattern(&text, 1, 10);
+            let mut locations = fm_index.locate(&pattern);
+            locations.sort();
+            let expected: Vec<P> = text.windows(pattern.len()).enumerate()
+                .filter(|(_, window)| {
+                    window.iter().zip(&pattern).all(|(a, b)| encoding_table.idx_of(*a) == encoding_table.idx_of(*b))
+                })
+                .map(|(position, _)| P::from_usize(position))
+                .collect();
+            assert_eq!(locations, expected);
+        }
+    }
+}
+
+#[test]
+fn blob_is_same_on_any_thread_count() {
+    // Over the length of a chunk of each thread
+    assert_same_blob_on_threads::<u32, Block2<u64>>(300_000);
+    assert_same_blob_on_threads::<u64, Block3<u128>>(100_001);
+    assert_same_blob_on_threads::<u32, Block3<u64>>(1_000);
+}
@@ -18,11 +18,10 @@ let builder = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(
     symbol_count,
     encoding_table,
 ).unwrap();
-// You have to prepare a blob to build the index.
-let blob_size = builder.blob_size();
-let mut blob = vec![0; blob_size];
-// Build the fm-index to the blob.
-builder.build(text, &mut blob).unwrap();
+// Build the fm-index to the blob aligned for the block.
+// (`builder.build` writes to the pre-allocated slice of `builder.blob_size()` bytes,
+//  which must be aligned to `B::ALIGN_SIZE`; `Vec<u8>` is not guaranteed to be.)
+let blob = builder.build_to_aligned_vec(text).unwrap();
 // Load the fm-index from the blob.
 let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob[..]).unwrap();
 
@@ -35,6 +34,9 @@ assert_eq!(count, 2);
 let mut locations = fm_index.locate(pattern);
 locations.sort();  // The locations may not be in order.
 assert_eq!(locations, vec![5,18]);
+//   - locate lazily, resolving each location when it is taken
+let first_location = fm_index.locate_iter(pattern).next().unwrap();
+assert!(first_location == 5 || first_location == 18);
 
 // When using EncodingTable, the last symbol is treated as wild card.
 // In the text, X, Y, and Z can match any other unindexed character
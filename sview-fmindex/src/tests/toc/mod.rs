@@ -0,0 +1,130 @@
+// Assert that the sections are located by the table of contents (TOC)
+//  - TOC header: section count (u32) and padding (u32)
+//  - TOC entry: kind (u32), flags (u32), offset (u64) and length (u64)
+
+use crate::{
+    AnyFmIndex, BlobInfo, FmIndex, FmIndexBuilder, LoadError,
+    blocks::Block2,
+    text_encoders::EncodingTable,
+};
+
+const TOC_START: usize = 16;
+const TOC_ENTRY_SIZE: usize = 24;
+const FLAG_REQUIRED: u32 = 1;
+
+fn build_blob() -> Vec<u8> {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    let text = b"CTCCGTACACCTGTTTCGTATCGGAXXYYZZACGTACGTTTGACA".to_vec();
+    let builder = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap();
+    let mut blob = vec![0; builder.blob_size()];
+    builder.build(text, &mut blob).unwrap();
+    blob
+}
+
+fn section_count(blob: &[u8]) -> usize {
+    u32::from_ne_bytes(blob[TOC_START..TOC_START + 4].try_into().unwrap()) as usize
+}
+fn entry_start(index: usize) -> usize {
+    TOC_START + 8 + index * TOC_ENTRY_SIZE
+}
+
+// Append the section at the end of the blob and list it at the end of the TOC
+fn with_extra_section(blob: &[u8], kind: u32, flags: u32, data: &[u8]) -> Vec<u8> {
+    let section_count = section_count(blob);
+    let old_header_end = entry_start(section_count);
+    // The TOC grows by an entry, which is a multiple of the alignment
+    let shift = TOC_ENTRY_SIZE as u64;
+
+    let mut new_blob = blob[..TOC_START].to_vec();
+    new_blob.extend_from_slice(&(section_count as u32 + 1).to_ne_bytes());
+    new_blob.extend_from_slice(&0_u32.to_ne_bytes());
+    for index in 0..section_count {
+        let mut entry = blob[entry_start(index)..entry_start(index + 1)].to_vec();
+        let offset = u64::from_ne_bytes(entry[8..16].try_into().unwrap()) + shift;
+        entry[8..16].copy_from_slice(&offset.to_ne_bytes());
+        new_blob.extend_from_slice(&entry);
+    }
+    new_blob.extend_from_slice(&kind.to_ne_bytes());
+    new_blob.extend_from_slice(&flags.to_ne_bytes());
+    new_blob.extend_from_slice(&(blob.len() as u64 + shift).to_ne_bytes());
+    new_blob.extend_from_slice(&(data.len() as u64).to_ne_bytes());
+    new_blob.extend_from_slice(&blob[old_header_end..]);
+    new_blob.extend_from_slice(data);
+    new_blob.resize(new_blob.len().div_ceil(8) * 8, 0);
+    new_blob
+}
+
+#[test]
+fn every_section_is_listed() {
+    let blob = build_blob();
+    let blob_info = BlobInfo::inspect(&blob).unwrap();
+    assert_eq!(section_count(&blob), 12);
+    assert_eq!(
+        blob_info.section("table of contents").unwrap().size,
+        8 + 12 * TOC_ENTRY_SIZE,
+    );
+    assert!(blob_info.sections.iter().all(|section| section.name != "unknown section"));
+}
+
+#[test]
+fn unknown_optional_section_is_skipped() {
+    let blob = build_blob();
+    let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob).unwrap();
+
+    let new_blob = with_extra_section(&blob, 1000, 0, b"optional data");
+    let new_fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&new_blob).unwrap();
+    assert_eq!(new_fm_index.locate(b"ACG"), fm_index.locate(b"ACG"));
+    assert!(new_fm_index.validate_structure().is_ok());
+    assert_eq!(AnyFmIndex::load(&new_blob).unwrap().locate(b"ACG"), vec![31, 35]);
+
+    let section = BlobInfo::inspect(&new_blob).unwrap().section("unknown section").unwrap().clone();
+    assert_eq!(section.offset, blob.len() + TOC_ENTRY_SIZE);
+    assert_eq!(section.size, b"optional data".len());
+}
+
+#[test]
+fn unknown_required_section_is_rejected() {
+    let blob = build_blob();
+    let new_blob = with_extra_section(&blob, 1000, FLAG_REQUIRED, b"required data");
+    assert!(matches!(
+        FmIndex::<u32, Block2<u64>, EncodingTable>::load(&new_blob),
+        Err(LoadError::UnknownRequiredSection(1000)),
+    ));
+}
+
+#[test]
+fn inconsistent_entry_is_rejected() {
+    let blob = build_blob();
+    let load = |blob: &[u8]| FmIndex::<u32, Block2<u64>, EncodingTable>::load(blob).map(|_| ());
+
+    // Suffix array is moved by the alignment, and overlaps the next section
+    let suffix_array_entry = (0..section_count(&blob))
+        .map(entry_start)
+        .find(|&start| u32::from_ne_bytes(blob[start..start + 4].try_into().unwrap()) == 19)
+        .unwrap();
+    let mut corrupted = blob.clone();
+    let offset = u64::from_ne_bytes(corrupted[suffix_array_entry + 8..suffix_array_entry + 16].try_into().unwrap());
+    corrupted[suffix_array_entry + 8..suffix_array_entry + 16].copy_from_slice(&(offset + 8).to_ne_bytes());
+    assert!(matches!(load(&corrupted), Err(LoadError::InvalidSection("table of contents"))));
+
+    // Length differs from the header
+    let mut corrupted = blob.clone();
+    let length = u64::from_ne_bytes(corrupted[suffix_array_entry + 16..suffix_array_entry + 24].try_into().unwrap());
+    corrupted[suffix_array_entry + 16..suffix_array_entry + 24].copy_from_slice(&(length - 4).to_ne_bytes());
+    assert!(matches!(load(&corrupted), Err(LoadError::InvalidSection("suffix array"))));
+
+    // Section ends after the blob
+    let mut corrupted = blob.clone();
+    let last_entry = entry_start(section_count(&blob) - 1);
+    corrupted[last_entry + 16..last_entry + 24].copy_from_slice(&u64::MAX.to_ne_bytes());
+    assert!(matches!(load(&corrupted), Err(LoadError::MismatchedBlobSize(..))));
+
+    // Section is listed twice
+    let mut corrupted = blob.clone();
+    corrupted.copy_within(suffix_array_entry..suffix_array_entry + 4, entry_start(0));
+    assert!(load(&corrupted).is_err());
+}
@@ -0,0 +1,110 @@
+// Assert that the tuned configurations fit in the blob size budget, and that the peak memory is estimated for each backend
+
+use crate::{
+    FmIndex, FmIndexBuilder, BuildError, TextEncoder,
+    blocks::Block2,
+    build_config::{LookupTableConfig, SuffixArrayConfig, SuffixSortConfig, QuerySpeed},
+    text_encoders::EncodingTable,
+};
+use crate::tests::random_data::{gen_rand_chr_list, gen_rand_text, gen_rand_pattern};
+
+const TEXT_LEN: usize = 100_000;
+
+fn encoding_table() -> EncodingTable {
+    EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"])
+}
+fn tuned_builder(blob_size_budget: usize, query_speed: QuerySpeed) -> Result<FmIndexBuilder<u32, Block2<u64>, EncodingTable>, BuildError> {
+    let encoding_table = encoding_table();
+    FmIndexBuilder::with_blob_size_budget(TEXT_LEN, encoding_table.symbol_count(), encoding_table, blob_size_budget, query_speed)
+}
+
+#[test]
+fn tuned_builder_fits_in_the_budget() {
+    for blob_size_budget in [100_000, 200_000, 500_000, 2_000_000] {
+        for query_speed in [QuerySpeed::Compact, QuerySpeed::Balanced, QuerySpeed::Fast] {
+            let builder = tuned_builder(blob_size_budget, query_speed).unwrap();
+            assert!(builder.blob_size() <= blob_size_budget);
+        }
+    }
+
+    // Faster preference takes the larger blob
+    let compact = tuned_builder(2_000_000, QuerySpeed::Compact).unwrap();
+    let fast = tuned_builder(2_000_000, QuerySpeed::Fast).unwrap();
+    assert!(compact.blob_size() < fast.blob_size());
+
+    // Too small budget
+    let result = tuned_builder(1000, QuerySpeed::Compact);
+    assert!(matches!(result, Err(BuildError::InvalidConfig(_))));
+}
+
+#[test]
+fn tuned_builder_gives_the_accurate_results() {
+    let text = gen_rand_text(&gen_rand_chr_list(4), TEXT_LEN, TEXT_LEN);
+    let encoding_table = encoding_table();
+    let blob = tuned_builder(200_000, QuerySpeed::Balanced).unwrap()
+        .build_to_aligned_vec(text.clone()).unwrap();
+    let fm_index = FmIndex::<u32, Block2<u64>, EncodingTable>::load(&blob).unwrap();
+
+    for _ in 0..20 {
+        let pattern = gen_rand_pattern(&text, 1, 10);
+        let mut locations = fm_index.locate(&pattern);
+        locations.sort();
+        let expected: Vec<u32> = text.windows(pattern.len()).enumerate()
+            .filter(|(_, window)| {
+                window.iter().zip(&pattern).all(|(a, b)| encoding_table.idx_of(*a) == encoding_table.idx_of(*b))
+            })
+            .map(|(position, _)| position as u32)
+            .collect();
+        assert_eq!(locations, expected);
+    }
+}
+
+#[test]
+fn vector_width_is_tuned() {
+    let encoding_table = encoding_table();
+    let tune = |blob_size_budget, query_speed| FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::tune_for_blob_size_budget(
+        TEXT_LEN, encoding_table.symbol_count(), &encoding_table, blob_size_budget, query_speed,
+    ).unwrap();
+
+    // Preferred width in the large budget
+    //  - Faster preference takes the denser suffix array and larger lookup table.
+    let fast = tune(2_000_000, QuerySpeed::Fast);
+    assert_eq!(fast.vector_bits, 64);
+    assert_eq!(fast.suffix_array_config, SuffixArrayConfig::Uncompressed);
+    assert!(matches!(fast.lookup_table_config, LookupTableConfig::KmerSize(_)));
+    let compact = tune(2_000_000, QuerySpeed::Compact);
+    assert_eq!(compact.vector_bits, 128);
+    assert_eq!(compact.suffix_array_config, SuffixArrayConfig::Compressed(32));
+    assert_eq!(compact.lookup_table_config, LookupTableConfig::None);
+
+    // The wider vector, of fewer rank checkpoints, leaves the room for the denser suffix array.
+    let tuned = tune(140_000, QuerySpeed::Fast);
+    assert_eq!(tuned.vector_bits, 128);
+    let builder = FmIndexBuilder::<u32, Block2<u128>, EncodingTable>::new(TEXT_LEN, encoding_table.symbol_count(), encoding_table.clone()).unwrap()
+        .set_suffix_array_config(tuned.suffix_array_config).unwrap()
+        .set_lookup_table_config(tuned.lookup_table_config).unwrap();
+    assert_eq!(builder.blob_size(), tuned.blob_size);
+    assert!(tuned.blob_size <= 140_000);
+    //  - The narrower vector of `B` fits only the sparser suffix array.
+    let narrower = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(TEXT_LEN, encoding_table.symbol_count(), encoding_table.clone()).unwrap()
+        .set_suffix_array_config(tuned.suffix_array_config).unwrap();
+    assert!(narrower.blob_size() > 140_000);
+}
+
+#[test]
+fn peak_build_memory_is_estimated() {
+    let encoding_table = encoding_table();
+    let builder = FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(TEXT_LEN, encoding_table.symbol_count(), encoding_table).unwrap()
+        .set_suffix_array_config(SuffixArrayConfig::Compressed(8)).unwrap();
+    let in_memory = builder.estimate_peak_build_memory();
+    // The blob, text and the whole suffix array at least
+    assert!(in_memory >= builder.blob_size() + (TEXT_LEN + 1) * (1 + size_of::<u32>()));
+
+    let builder = builder.set_suffix_sort_config(SuffixSortConfig::ExternalMemory {
+        temp_dir: std::env::temp_dir(),
+        memory_budget: SuffixSortConfig::MIN_MEMORY_BUDGET,
+    }).unwrap();
+    let external_memory = builder.estimate_peak_build_memory();
+    assert!(external_memory >= builder.blob_size() + SuffixSortConfig::MIN_MEMORY_BUDGET);
+    assert!(external_memory < builder.blob_size() + TEXT_LEN + 2 * SuffixSortConfig::MIN_MEMORY_BUDGET);
+}
@@ -1,5 +1,16 @@
+use alloc::vec::Vec;
 use num_integer::div_rem;
 
+/// Serialization of the position with `capwriter`, which needs the `std` feature
+#[cfg(feature = "std")]
+pub trait SerializablePosition: capwriter::Save + capwriter::Load {}
+#[cfg(feature = "std")]
+impl<T: capwriter::Save + capwriter::Load> SerializablePosition for T {}
+#[cfg(not(feature = "std"))]
+pub trait SerializablePosition {}
+#[cfg(not(feature = "std"))]
+impl<T> SerializablePosition for T {}
+
 /**
 Type for the position in index.
 
@@ -13,18 +24,17 @@ pub trait Position:
     + Clone
     + Ord + PartialOrd + Eq + PartialEq
     + Send + Sync
-    + std::fmt::Debug
-    + std::ops::Div<Output = Self>
-    + std::ops::Rem<Output = Self>
-    + std::ops::Add<Output = Self>
-    + std::ops::AddAssign<Self>
-    + std::ops::Sub<Output = Self>
-    + std::cmp::PartialOrd
+    + core::fmt::Debug
+    + core::ops::Div<Output = Self>
+    + core::ops::Rem<Output = Self>
+    + core::ops::Add<Output = Self>
+    + core::ops::AddAssign<Self>
+    + core::ops::Sub<Output = Self>
+    + core::cmp::PartialOrd
     + zerocopy::FromBytes
     + zerocopy::IntoBytes
     + zerocopy::Immutable
-    + capwriter::Save
-    + capwriter::Load
+    + SerializablePosition
 {
     const ZERO: Self;
     const ONE: Self;
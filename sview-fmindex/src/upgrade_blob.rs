@@ -0,0 +1,86 @@
+use crate::{
+    FmIndex, LoadError,
+    // traits
+    Position, Block, TextEncoder,
+    components::{MagicNumber, TypeDescriptor, ChecksumHeader, Toc, SectionKind},
+    load_from_blob::BlobHeaders,
+    any_fm_index::dyn_types,
+};
+
+/// Size of the blob upgraded to the current format version
+///
+/// The blobs of version 0.0 do not record their types,
+/// so the `type_descriptor` is the types that the blob was built with.
+/// The blobs that record their types must have the same types.
+pub fn upgraded_blob_size(old_blob: &[u8], type_descriptor: &TypeDescriptor) -> Result<usize, LoadError> {
+    dyn_types(type_descriptor)?.upgraded_blob_size(old_blob)
+}
+
+/// Rewrite the blob of a previous format version in the layout of the current version
+///
+/// The blobs of version 0.0 do not record their types,
+/// so the `type_descriptor` is the types that the blob was built with.
+/// The blobs that record their types must have the same types.
+pub fn upgrade_blob(old_blob: &[u8], new_blob: &mut [u8], type_descriptor: &TypeDescriptor) -> Result<(), LoadError> {
+    dyn_types(type_descriptor)?.upgrade_blob(old_blob, new_blob)
+}
+
+impl<P: Position, B: Block, E: TextEncoder> FmIndex<'_, P, B, E> {
+    /// Size of the blob upgraded to the current format version
+    pub fn upgraded_blob_size(old_blob: &[u8]) -> Result<usize, LoadError> {
+        let (headers, _) = read_headers_to_upgrade::<P, B, E>(old_blob)?;
+        let metadata_len = headers.metadata(old_blob).map(|metadata| metadata.len());
+        Ok(headers.current_toc::<P, B>(metadata_len).aligned_end::<B>() as usize)
+    }
+    /// Rewrite the blob of a previous format version in the layout of the current version
+    ///
+    /// The bodies are copied as they are, and only the headers and checksums are written again.
+    /// The metadata is copied, and the sections unknown to this version are not.
+    /// The `new_blob` must be aligned to the block and have the size of `upgraded_blob_size`.
+    pub fn upgrade_blob(old_blob: &[u8], new_blob: &mut [u8]) -> Result<(), LoadError> {
+        let (mut headers, old_toc) = read_headers_to_upgrade::<P, B, E>(old_blob)?;
+        let metadata = headers.metadata(old_blob);
+        let new_toc = headers.current_toc::<P, B>(metadata.map(|metadata| metadata.len()));
+        let blob_size = new_toc.aligned_end::<B>() as usize;
+        if new_blob.len() != blob_size {
+            return Err(LoadError::MismatchedBlobSize(blob_size, new_blob.len()));
+        }
+
+        // Write headers with empty checksums, and bodies
+        new_blob.fill(0);
+        headers.toc = new_toc;
+        headers.checksum_header = Some(ChecksumHeader::default());
+        headers.write_to_blob(new_blob);
+        headers.body_sections::<P, B>().iter().for_each(|section_size| {
+            let (Some(old_entry), Some(new_entry)) = (old_toc.entry(section_size.kind), headers.toc.entry(section_size.kind)) else {
+                return;
+            };
+            let (old_offset, new_offset) = (old_entry.offset as usize, new_entry.offset as usize);
+            new_blob[new_offset..new_offset + section_size.raw_size]
+                .copy_from_slice(&old_blob[old_offset..old_offset + section_size.raw_size]);
+        });
+        if let (Some(metadata), Some(new_entry)) = (metadata, headers.toc.entry(SectionKind::Metadata)) {
+            let new_offset = new_entry.offset as usize;
+            new_blob[new_offset..new_offset + metadata.len()].copy_from_slice(metadata);
+        }
+
+        // Write checksums
+        let checksum_header = FmIndex::<P, B, E>::load(new_blob)?.compute_checksums();
+        headers.checksum_header = Some(checksum_header);
+        headers.write_to_blob(new_blob);
+
+        Ok(())
+    }
+}
+
+// Headers of the old blob with the magic number and type descriptor of the current version,
+// and the TOC of the old blob
+fn read_headers_to_upgrade<P: Position, B: Block, E: TextEncoder>(
+    old_blob: &[u8],
+) -> Result<(BlobHeaders<E>, Toc), LoadError> {
+    let (mut headers, _) = BlobHeaders::<E>::read::<P, B>(old_blob)?;
+    let old_toc = headers.toc.clone();
+    headers.magic_number = MagicNumber::new();
+    headers.type_descriptor = TypeDescriptor::of::<P, B, E>();
+    Ok((headers, old_toc))
+}
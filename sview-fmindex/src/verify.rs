@@ -0,0 +1,94 @@
+use zerocopy::IntoBytes;
+
+use crate::{
+    FmIndex,
+    // traits
+    Position, Block, TextEncoder,
+    components::{ChecksumHeader, PREFIX_SIZE},
+};
+
+/// Error type for verifying the integrity of fm-index blob
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    /// The checksum of a section does not match the checksum written at build.
+    #[error("Corrupted section: the checksum of the {section} is {found:#010x}, but {expected:#010x} was written at build.")]
+    ChecksumMismatch {
+        section: &'static str,
+        expected: u32,
+        found: u32,
+    },
+
+    /// The blob of the format version 0.0 does not have checksums.
+    #[error("Missing checksums: the blob was built before checksums were recorded. Upgrade it with `upgrade_blob` first.")]
+    MissingChecksums,
+
+    /// A section violates the invariants that the queries rely on.
+    #[error("Invalid structure: the {section} {reason}.")]
+    InvalidStructure {
+        section: &'static str,
+        reason: &'static str,
+    },
+}
+
+impl<P: Position, B: Block, E: TextEncoder> FmIndex<'_, P, B, E> {
+    /// Verify the checksums of every header, body and metadata section in blob
+    ///
+    /// All sections are read, so it takes time proportional to the blob size.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        let checksum_header = self.checksum_header.as_ref().ok_or(VerifyError::MissingChecksums)?;
+        let computed = self.compute_checksums();
+        checksum_header.sections().into_iter()
+            .zip(computed.sections())
+            .try_for_each(|((section, expected), (_, found))| {
+                if expected == found {
+                    Ok(())
+                } else {
+                    Err(VerifyError::ChecksumMismatch { section, expected, found })
+                }
+            })
+    }
+    /// Validate the structural invariants that the queries rely on
+    ///
+    /// Use this before querying the blobs from untrusted sources.
+    /// Every body section is scanned once, so it takes time proportional to the blob size.
+    pub fn validate_structure(&self) -> Result<(), VerifyError> {
+        let symbol_count = self.count_array_header.symbol_count;
+        if !self.text_encoder.encodes_within(symbol_count) {
+            return Err(VerifyError::InvalidStructure {
+                section: "text encoder",
+                reason: "encodes a symbol out of the symbol count",
+            });
+        }
+        let text_len = self.count_array_view.validate_structure(symbol_count)?;
+        self.suffix_array_view.validate_structure(text_len)?;
+        self.bwm_view.validate_structure(text_len, &self.count_array_view)?;
+        Ok(())
+    }
+    pub(crate) fn compute_checksums(&self) -> ChecksumHeader {
+        let mut checksum_header = ChecksumHeader {
+            prefix: ChecksumHeader::checksum_of_chain(&[
+                self.magic_number.as_bytes(),
+                self.type_descriptor.as_bytes(),
+                self.toc_bytes(),
+            ]),
+            text_encoder: ChecksumHeader::checksum_of(self.text_encoder.as_bytes()),
+            count_array_header: ChecksumHeader::checksum_of(self.count_array_header.as_bytes()),
+            suffix_array_header: ChecksumHeader::checksum_of(self.suffix_array_header.as_bytes()),
+            bwm_header: ChecksumHeader::checksum_of(self.bwm_header.as_bytes()),
+            metadata: self.metadata().map_or(0, ChecksumHeader::checksum_of),
+            ..Default::default()
+        };
+        self.count_array_view.write_checksums(&mut checksum_header);
+        self.suffix_array_view.write_checksums(&mut checksum_header);
+        self.bwm_view.write_checksums(&mut checksum_header);
+        checksum_header
+    }
+    // TOC as written in blob, which is covered by the checksum of the prefix
+    fn toc_bytes(&self) -> &[u8] {
+        if self.magic_number.is_untyped_version() {
+            &[]
+        } else {
+            &self.source_blob[PREFIX_SIZE..PREFIX_SIZE + self.toc.raw_size()]
+        }
+    }
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "std")]
+
+// Assert that the estimated peak memory of the build is an upper bound of the measured one
+//  - The allocations are tracked by the global allocator of this test binary,
+//    so that no other tests are built at the same time.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rand::Rng;
+use sview_fmindex::{
+    FmIndexBuilder, Position,
+    build_config::{SuffixArrayConfig, SuffixSortConfig},
+    Block, blocks::{Block2, Block3},
+    text_encoders::EncodingTable,
+};
+
+// ================================================
+// Tracking allocator
+// ================================================
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+struct TrackingAllocator;
+
+impl TrackingAllocator {
+    fn add(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::SeqCst) + size;
+        PEAK_ALLOCATED.fetch_max(allocated, Ordering::SeqCst);
+    }
+    fn sub(size: usize) {
+        ALLOCATED.fetch_sub(size, Ordering::SeqCst);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            Self::add(layout.size());
+        }
+        ptr
+    }
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            Self::add(layout.size());
+        }
+        ptr
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        Self::sub(layout.size());
+    }
+    // Both of the old and new allocations are counted during the reallocation.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::add(new_size);
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if new_ptr.is_null() {
+            Self::sub(new_size);
+        } else {
+            Self::sub(layout.size());
+        }
+        new_ptr
+    }
+}
+
+#[global_allocator]
+static GLOBAL: TrackingAllocator = TrackingAllocator;
+
+// Peak of the memory allocated while running `f`, above the memory allocated before it
+fn measure_peak_memory<T>(f: impl FnOnce() -> T) -> usize {
+    let allocated_before = ALLOCATED.load(Ordering::SeqCst);
+    PEAK_ALLOCATED.store(allocated_before, Ordering::SeqCst);
+    let result = f();
+    let peak_allocated = PEAK_ALLOCATED.load(Ordering::SeqCst);
+    drop(result);
+    peak_allocated - allocated_before
+}
+
+// ================================================
+// Builds
+// ================================================
+const SYMBOLS: &[u8] = b"ACGT";
+
+fn random_text(len: usize) -> Vec<u8> {
+    let mut rng = rand::rng();
+    (0..len).map(|_| SYMBOLS[rng.random_range(0..SYMBOLS.len())]).collect()
+}
+
+fn assert_estimate_is_upper_bound<P: Position, B: Block>(
+    text: &[u8],
+    suffix_array_config: SuffixArrayConfig,
+    suffix_sort_config: SuffixSortConfig,
+) {
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    let builder = FmIndexBuilder::<P, B, EncodingTable>::new(
+        text.len(),
+        encoding_table.symbol_count(),
+        encoding_table,
+    ).unwrap()
+        .set_suffix_array_config(suffix_array_config).unwrap()
+        .set_suffix_sort_config(suffix_sort_config.clone()).unwrap();
+    let estimated = builder.estimate_peak_build_memory();
+
+    
+    let measured = measure_peak_memory(|| {
+        let mut text_buffer = Vec::with_capacity(text.len() + 1);
+        text_buffer.extend_from_slice(text);
+        builder.build_to_aligned_vec(text_buffer).unwrap()
+    });
+    assert!(
+        measured <= estimated,
+        "Measured peak memory {} is over the estimate {} ({:?}, {:?})",
+        measured, estimated, suffix_array_config, suffix_sort_config,
+    );
+}
+
+// The builds are run in one test, so that the allocations of the others are not measured.
+#[test]
+fn estimated_peak_build_memory_is_upper_bound() {
+    // The global thread pool of the `parallel` feature is created by the first build, which is not measured.
+    let encoding_table = EncodingTable::from_symbols(&[b"A", b"C", b"G", b"T"]);
+    FmIndexBuilder::<u32, Block2<u64>, EncodingTable>::new(100, encoding_table.symbol_count(), encoding_table).unwrap()
+        .build_to_aligned_vec(random_text(100)).unwrap();
+
+    let random = random_text(1 << 20);
+    // Repeats of a random block, of which the reduced problems recurse deeply
+    let repetitive = random_text(1000).repeat(1000);
+
+    for text in [&random, &repetitive] {
+        assert_estimate_is_upper_bound::<u32, Block2<u64>>(text, SuffixArrayConfig::Uncompressed, SuffixSortConfig::InMemory);
+        assert_estimate_is_upper_bound::<u64, Block3<u128>>(text, SuffixArrayConfig::Compressed(4), SuffixSortConfig::InMemory);
+    }
+
+    let temp_dir = std::env::temp_dir().join(format!("sview-fmindex-peak-build-memory-{}", std::process::id()));
+    std::fs::create_dir_all(&temp_dir).unwrap();
+    let external_memory = SuffixSortConfig::ExternalMemory {
+        temp_dir: temp_dir.clone(),
+        memory_budget: SuffixSortConfig::MIN_MEMORY_BUDGET,
+    };
+    for text in [&random, &repetitive] {
+        assert_estimate_is_upper_bound::<u32, Block2<u64>>(text, SuffixArrayConfig::Compressed(2), external_memory.clone());
+    }
+    std::fs::remove_dir_all(&temp_dir).unwrap();
+}